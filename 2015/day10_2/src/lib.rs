@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+
 pub fn generate_next(s: &str) -> String {
     let chars: Vec<_> = s.chars().collect();
     let mut output = String::new();
@@ -16,6 +20,145 @@ pub fn generate_next(s: &str) -> String {
     output
 }
 
+// lengths of up to `limit` leading runs of `s`, with their digit
+fn leading_runs(s: &[u8], limit: usize) -> Vec<(u8, usize)> {
+    let mut runs: Vec<(u8, usize)> = vec![];
+    for &digit in s {
+        match runs.last_mut() {
+            Some((last, count)) if *last == digit => *count += 1,
+            _ => {
+                if runs.len() == limit {
+                    break;
+                }
+                runs.push((digit, 1));
+            }
+        }
+    }
+    runs
+}
+
+// the right-hand side of a split after a 2, per Conway's splitting theorem:
+// [1^1 X^1, [1^3, [3^1 X^(not 3), or [n^(0 or 1)] with n >= 4
+fn splits_after_two(right: &[u8]) -> bool {
+    match leading_runs(right, 2)[..] {
+        [] => true,
+        [(b'1', 1), (_, 1), ..] | [(b'1', 3), ..] => true,
+        [(b'3', 1)] => true,
+        [(b'3', 1), (_, count)] => count != 3,
+        [(digit, 1)] => digit >= b'4',
+        _ => false,
+    }
+}
+
+// Conway's splitting theorem: in a string at least 2 days old, `left` and
+// `right` never interact again if `left` ends in 2 and `right` starts as in
+// `splits_after_two`, or `left` ends in anything else and `right` is a run
+// of exactly two 2s followed by such a start.
+fn splits(left: &str, right: &str) -> bool {
+    let (left, right) = (left.as_bytes(), right.as_bytes());
+    if left[left.len() - 1] == b'2' {
+        return splits_after_two(right);
+    }
+    match leading_runs(right, 1)[..] {
+        [(b'2', 2)] => splits_after_two(&right[2..]),
+        _ => false,
+    }
+}
+
+// splits a sequence at least 2 days old into the largest number of parts
+// that evolve independently; for sequences of digits 1 to 3 these are
+// Conway's elements
+pub fn split_elements(s: &str) -> Vec<&str> {
+    let bytes = s.as_bytes();
+    let mut elements = vec![];
+    let mut start = 0;
+    for i in 1..s.len() {
+        if bytes[i - 1] != bytes[i] && splits(&s[start..i], &s[i..]) {
+            elements.push(&s[start..i]);
+            start = i;
+        }
+    }
+    if !s.is_empty() {
+        elements.push(&s[start..]);
+    }
+    elements
+}
+
+// evolves how many times each element occurs rather than the sequence itself
+#[derive(Debug, Default)]
+pub struct Audioactive {
+    elements: Vec<String>,
+    ids: HashMap<String, usize>,
+    // ids of the elements each element decays into, filled in lazily
+    decays: Vec<Option<Vec<usize>>>,
+}
+
+impl Audioactive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn id(&mut self, element: &str) -> usize {
+        if let Some(&id) = self.ids.get(element) {
+            return id;
+        }
+        self.elements.push(element.to_owned());
+        self.decays.push(None);
+        self.ids.insert(element.to_owned(), self.elements.len() - 1);
+        self.elements.len() - 1
+    }
+
+    fn decay(&mut self, id: usize) -> Vec<usize> {
+        if let Some(decay) = &self.decays[id] {
+            return decay.clone();
+        }
+        let next = generate_next(&self.elements[id]);
+        let decay = split_elements(&next)
+            .into_iter()
+            .map(|element| self.id(element))
+            .collect::<Vec<_>>();
+        self.decays[id] = Some(decay.clone());
+        decay
+    }
+
+    pub fn element_counts(&mut self, start: &str, iterations: usize) -> HashMap<String, BigUint> {
+        // the splitting theorem only holds from the second day on, so the
+        // first two days are computed directly
+        let mut sequence = start.to_owned();
+        for _ in 0..iterations.min(2) {
+            sequence = generate_next(&sequence);
+        }
+        let mut counts = HashMap::<usize, BigUint>::new();
+        if iterations < 2 {
+            *counts.entry(self.id(&sequence)).or_default() += 1u32;
+        } else {
+            for element in split_elements(&sequence) {
+                *counts.entry(self.id(element)).or_default() += 1u32;
+            }
+        }
+        for _ in 2..iterations {
+            let mut next = HashMap::<usize, BigUint>::new();
+            for (id, count) in counts {
+                for product in self.decay(id) {
+                    *next.entry(product).or_default() += &count;
+                }
+            }
+            counts = next;
+        }
+        counts
+            .into_iter()
+            .map(|(id, count)| (self.elements[id].clone(), count))
+            .collect()
+    }
+
+    pub fn length_after(&mut self, start: &str, iterations: usize) -> BigUint {
+        self.element_counts(start, iterations)
+            .into_iter()
+            .map(|(element, count)| count * element.len())
+            .sum()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,4 +172,86 @@ mod tests {
     fn generate_next_test_2() {
         assert_eq!(generate_next("1233"), "111223".to_string());
     }
+
+    #[test]
+    fn splits_test_1() {
+        assert!(splits("2", "1312"));
+        assert!(splits("2", "111"));
+        assert!(splits("2", "3112"));
+        assert!(!splits("2", "31112"));
+        assert!(splits("2", "4"));
+        assert!(!splits("2", "45"));
+        assert!(splits("13", "2213"));
+        assert!(!splits("13", "22213"));
+        assert!(!splits("13", "1312"));
+    }
+
+    #[test]
+    fn split_elements_test_1() {
+        // Hf decays into Hf + Pa + H + Ca + Li
+        assert_eq!(
+            split_elements("111321321122312312211322212221121123222112"),
+            ["11132", "13211", "22", "312", "312211322212221121123222112"]
+        );
+        assert_eq!(split_elements("22"), ["22"]);
+        assert_eq!(split_elements(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn length_after_test_1() {
+        let mut engine = Audioactive::new();
+        for start in ["1", "3", "1113222113", "1321131112", "22", "4112"] {
+            let mut sequence = start.to_string();
+            for iterations in 0..30 {
+                assert_eq!(
+                    engine.length_after(start, iterations),
+                    BigUint::from(sequence.len())
+                );
+                sequence = generate_next(&sequence);
+            }
+        }
+    }
+
+    #[test]
+    fn length_after_test_2() {
+        let mut engine = Audioactive::new();
+        let mut sequence = "1".to_string();
+        for _ in 0..40 {
+            sequence = generate_next(&sequence);
+        }
+        let counts = engine.element_counts("1", 40);
+        let mut rebuilt = 0u32;
+        for element in split_elements(&sequence) {
+            rebuilt += 1;
+            assert!(counts.contains_key(element));
+        }
+        assert_eq!(counts.values().sum::<BigUint>(), BigUint::from(rebuilt));
+        // the common elements of Conway's periodic table
+        assert_eq!(engine.element_counts("1", 100).len(), 92);
+    }
+
+    #[test]
+    fn length_after_test_3() {
+        let mut engine = Audioactive::new();
+        let length = engine.length_after("1", 2000);
+        assert_eq!(length.to_string().len(), 231);
+        // successive lengths grow by Conway's constant
+        let ratio = engine.length_after("1", 2001) * 1_000_000u32 / length;
+        assert_eq!(ratio, BigUint::from(1_303_577u32));
+    }
+
+    #[test]
+    fn length_after_test_4() {
+        let mut engine = Audioactive::new();
+        for start in ["4112", "123456789", "22233311", "2", "98765", "3332221115"] {
+            let mut sequence = start.to_string();
+            for iterations in 0..=45 {
+                assert_eq!(
+                    engine.length_after(start, iterations),
+                    BigUint::from(sequence.len())
+                );
+                sequence = generate_next(&sequence);
+            }
+        }
+    }
 }
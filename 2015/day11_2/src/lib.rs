@@ -1,23 +1,4 @@
-fn increment_password(pass: &str) -> String {
-    let mut inc = true;
-    let mut new: Vec<_> = pass
-        .bytes()
-        .rev()
-        .map(|c| {
-            if inc {
-                if c == b'z' {
-                    return b'a';
-                } else {
-                    inc = false;
-                    return c + 1;
-                }
-            }
-            c
-        })
-        .collect();
-    new.reverse();
-    String::from_utf8(new).unwrap()
-}
+use thiserror::Error;
 
 fn contains_double_pair(s: &str) -> bool {
     let chars = s.chars().collect::<Vec<_>>();
@@ -34,10 +15,6 @@ fn contains_double_pair(s: &str) -> bool {
     false
 }
 
-fn doesnt_contain_iol(s: &str) -> bool {
-    !s.chars().any(|c| c == 'i' || c == 'o' || c == 'l')
-}
-
 fn contains_increasing_triple(s: &str) -> bool {
     s.bytes()
         .collect::<Vec<_>>()
@@ -45,23 +22,249 @@ fn contains_increasing_triple(s: &str) -> bool {
         .any(|w| w[1] == (w[0] + 1) && w[2] == (w[1] + 1))
 }
 
-pub fn next_password(s: &str) -> String {
-    let mut pass = s.to_owned();
-    loop {
-        pass = increment_password(&pass);
-        if contains_double_pair(&pass)
-            && doesnt_contain_iol(&pass)
-            && contains_increasing_triple(&pass)
-        {
-            return pass;
+pub trait Rule {
+    fn is_satisfied(&self, password: &str) -> bool;
+
+    // letters that can never appear in a valid password, which lets the
+    // search skip every candidate containing them
+    fn forbidden_letters(&self) -> Vec<char> {
+        vec![]
+    }
+}
+
+pub struct IncreasingTriple;
+
+impl Rule for IncreasingTriple {
+    fn is_satisfied(&self, password: &str) -> bool {
+        contains_increasing_triple(password)
+    }
+}
+
+pub struct DoublePair;
+
+impl Rule for DoublePair {
+    fn is_satisfied(&self, password: &str) -> bool {
+        contains_double_pair(password)
+    }
+}
+
+pub struct ForbiddenLetters(pub Vec<char>);
+
+impl Rule for ForbiddenLetters {
+    fn is_satisfied(&self, password: &str) -> bool {
+        !password.chars().any(|c| self.0.contains(&c))
+    }
+
+    fn forbidden_letters(&self) -> Vec<char> {
+        self.0.clone()
+    }
+}
+
+pub fn doesnt_contain_iol(s: &str) -> bool {
+    ForbiddenLetters(vec!['i', 'o', 'l']).is_satisfied(s)
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PolicyError {
+    #[error("password must have {expected} letters, found {found}")]
+    WrongLength { expected: usize, found: usize },
+    #[error("letter not in alphabet: {0}")]
+    UnknownLetter(char),
+}
+
+pub struct Policy {
+    alphabet: Vec<char>,
+    length: Option<usize>,
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl Policy {
+    // letters of `alphabet` must be given in increasing order
+    pub fn new(alphabet: &str) -> Self {
+        Policy {
+            alphabet: alphabet.chars().collect(),
+            length: None,
+            rules: vec![],
+        }
+    }
+
+    // the rules of the puzzle
+    pub fn santa() -> Self {
+        Policy::new("abcdefghijklmnopqrstuvwxyz")
+            .with_length(8)
+            .with_rule(IncreasingTriple)
+            .with_rule(ForbiddenLetters(vec!['i', 'o', 'l']))
+            .with_rule(DoublePair)
+    }
+
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    pub fn with_rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    pub fn is_valid(&self, password: &str) -> bool {
+        let length = password.chars().count();
+        self.length.is_none_or(|expected| expected == length)
+            && password.chars().all(|c| self.alphabet.contains(&c))
+            && self.rules.iter().all(|rule| rule.is_satisfied(password))
+    }
+
+    // valid passwords following `password` in increasing order, ending when
+    // every letter has reached the end of the alphabet
+    pub fn successors(&self, password: &str) -> Result<Successors<'_>, PolicyError> {
+        let found = password.chars().count();
+        if let Some(expected) = self.length.filter(|&expected| expected != found) {
+            return Err(PolicyError::WrongLength { expected, found });
+        }
+        let forbidden = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.forbidden_letters())
+            .collect::<Vec<_>>();
+        let allowed = self
+            .alphabet
+            .iter()
+            .copied()
+            .filter(|c| !forbidden.contains(c))
+            .collect::<Vec<_>>();
+        let digits = password
+            .chars()
+            .map(|c| {
+                self.alphabet
+                    .iter()
+                    .position(|&letter| letter == c)
+                    .ok_or(PolicyError::UnknownLetter(c))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut current = Vec::with_capacity(digits.len());
+        let mut pending = false;
+        for (i, &digit) in digits.iter().enumerate() {
+            let letter = self.alphabet[digit];
+            let rank = allowed.partition_point(|&c| c < letter);
+            current.push(rank);
+            // the first forbidden letter invalidates every candidate until
+            // it changes, so jump straight to the next allowed letter there
+            if allowed.get(rank) != Some(&letter) {
+                current.resize(digits.len(), 0);
+                pending = true;
+                if !carry(&mut current, i, allowed.len()) {
+                    current.clear();
+                    pending = false;
+                }
+                break;
+            }
+        }
+        Ok(Successors {
+            policy: self,
+            allowed,
+            current: Some(current),
+            pending,
+        })
+    }
+
+    pub fn next_n(&self, password: &str, n: usize) -> Result<Vec<String>, PolicyError> {
+        Ok(self.successors(password)?.take(n).collect())
+    }
+}
+
+// makes digit `i` valid after it was set to `base` or more by carrying into
+// the digits on its left; false if the password overflows
+fn carry(digits: &mut [usize], mut i: usize, base: usize) -> bool {
+    while digits[i] >= base {
+        digits[i] = 0;
+        if i == 0 {
+            return false;
+        }
+        i -= 1;
+        digits[i] += 1;
+    }
+    true
+}
+
+// odometer over the allowed letters only
+pub struct Successors<'a> {
+    policy: &'a Policy,
+    allowed: Vec<char>,
+    current: Option<Vec<usize>>,
+    // whether `current` is a candidate that has not been checked yet
+    pending: bool,
+}
+
+impl Iterator for Successors<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let current = self.current.as_mut()?;
+            if !self.pending {
+                if current.is_empty() {
+                    self.current = None;
+                    return None;
+                }
+                let last = current.len() - 1;
+                current[last] += 1;
+                if !carry(current, last, self.allowed.len()) {
+                    self.current = None;
+                    return None;
+                }
+            }
+            self.pending = false;
+            let password = current
+                .iter()
+                .map(|&rank| self.allowed[rank])
+                .collect::<String>();
+            if self
+                .policy
+                .rules
+                .iter()
+                .all(|rule| rule.is_satisfied(&password))
+            {
+                return Some(password);
+            }
         }
     }
 }
 
+// `None` if `s` has letters outside the alphabet or no valid successor
+pub fn next_password(s: &str) -> Option<String> {
+    Policy::santa()
+        .with_length(s.chars().count())
+        .successors(s)
+        .ok()?
+        .next()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // one candidate at a time, wrapping around after the last one
+    fn increment_password(pass: &str) -> String {
+        let mut inc = true;
+        let mut new: Vec<_> = pass
+            .bytes()
+            .rev()
+            .map(|c| {
+                if inc {
+                    if c == b'z' {
+                        return b'a';
+                    } else {
+                        inc = false;
+                        return c + 1;
+                    }
+                }
+                c
+            })
+            .collect();
+        new.reverse();
+        String::from_utf8(new).unwrap()
+    }
+
     #[test]
     fn increment_password_test_1() {
         assert_eq!(increment_password("vneorvzzz"), "vneorwaaa");
@@ -87,6 +290,15 @@ mod tests {
         assert!(!doesnt_contain_iol("diofvnboer"));
     }
 
+    #[test]
+    fn forbidden_letters_test_1() {
+        let rule = ForbiddenLetters(vec!['q', 'x']);
+        assert!(rule.is_satisfied("abcdef"));
+        assert!(!rule.is_satisfied("abxdef"));
+        assert_eq!(rule.forbidden_letters(), ['q', 'x']);
+        assert!(IncreasingTriple.forbidden_letters().is_empty());
+    }
+
     #[test]
     fn contains_increasing_triple_test_1() {
         assert!(contains_increasing_triple("dnbufgnefgww"));
@@ -99,6 +311,85 @@ mod tests {
 
     #[test]
     fn next_password_test_1() {
-        assert_eq!(next_password("rtvvtbqqrrc"), "rtvvtbqqrsa");
+        assert_eq!(next_password("rtvvtbqqrrc").unwrap(), "rtvvtbqqrsa");
+    }
+
+    #[test]
+    fn next_password_test_2() {
+        // examples from the puzzle description
+        assert_eq!(next_password("abcdefgh").unwrap(), "abcdffaa");
+        assert_eq!(next_password("ghijklmn").unwrap(), "ghjaabcc");
+        assert!(!Policy::santa().is_valid("hijklmmn"));
+        assert!(!Policy::santa().is_valid("abbceffg"));
+        assert!(Policy::santa().is_valid("ghjaabcc"));
+    }
+
+    #[test]
+    fn next_password_test_3() {
+        assert_eq!(next_password("zzzzzzzz"), None);
+        assert_eq!(next_password("abcDefgh"), None);
+    }
+
+    #[test]
+    fn policy_next_n_test_1() {
+        // matches trying every candidate on a small alphabet
+        let policy = || {
+            Policy::new("abcdef")
+                .with_length(5)
+                .with_rule(ForbiddenLetters(vec!['c']))
+                .with_rule(DoublePair)
+        };
+        let reference = |start: &str| {
+            let mut pass = start.to_owned();
+            let mut found = vec![];
+            loop {
+                pass = increment_password(&pass);
+                if pass.chars().any(|c| c > 'f') {
+                    // skip to the next candidate within the alphabet
+                    let i = pass.find(|c| c > 'f').unwrap();
+                    pass = format!("{}{}", &pass[..i], "z".repeat(pass.len() - i));
+                    if pass.chars().all(|c| c == 'z') {
+                        return found;
+                    }
+                    continue;
+                }
+                if policy().is_valid(&pass) {
+                    found.push(pass.clone());
+                }
+            }
+        };
+        for start in ["aaaaa", "abcde", "ccccc", "eeffc", "ffffa"] {
+            let expected = reference(start);
+            assert_eq!(policy().next_n(start, 10000).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn policy_successors_test_1() {
+        let policy = Policy::santa();
+        assert_eq!(
+            policy.successors("abc").err(),
+            Some(PolicyError::WrongLength {
+                expected: 8,
+                found: 3
+            })
+        );
+        assert_eq!(
+            policy.successors("abcdefgH").err(),
+            Some(PolicyError::UnknownLetter('H'))
+        );
+        assert_eq!(
+            policy.next_n("ghijklmn", 3).unwrap(),
+            ["ghjaabcc", "ghjbbcdd", "ghjccdee"]
+        );
+        assert_eq!(policy.next_n("zzzzzzzz", 3).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn policy_successors_test_2() {
+        // the first candidate is skipped past every password containing q
+        let policy = Policy::new("abpqrs").with_rule(ForbiddenLetters(vec!['q']));
+        let mut successors = policy.successors("apss").unwrap();
+        assert_eq!(successors.next().as_deref(), Some("araa"));
     }
 }
@@ -1,9 +1,10 @@
 use std::fs::read_to_string;
 
-use day11_2::next_password;
+use day11_2::Policy;
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
     let input = input.trim().to_string();
-    println!("next password: {}", next_password(&next_password(&input)));
+    let passwords = Policy::santa().next_n(&input, 2).unwrap();
+    println!("next password: {}", passwords[1]);
 }
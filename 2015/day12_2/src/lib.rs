@@ -1,4 +1,14 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
 use serde_json::Value;
+use thiserror::Error;
 
 pub fn count_numbers(json: &Value) -> Option<i64> {
     match json {
@@ -29,6 +39,431 @@ fn has_red(json: &Value) -> bool {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scalar {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn format_path(path: &[Segment]) -> String {
+    let mut formatted = String::from("$");
+    for segment in path {
+        match segment {
+            Segment::Key(key)
+                if !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                formatted.push('.');
+                formatted.push_str(key);
+            }
+            Segment::Key(key) => formatted.push_str(&format!("[{}]", Value::from(key.as_str()))),
+            Segment::Index(index) => formatted.push_str(&format!("[{index}]")),
+        }
+    }
+    formatted
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum SelectorError {
+    #[error("selector must start with '$'")]
+    MissingRoot,
+    #[error("unexpected character at position {0}")]
+    UnexpectedCharacter(usize),
+    #[error("unterminated bracket at position {0}")]
+    UnterminatedBracket(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Matcher {
+    Key(String),
+    Index(usize),
+    Any,
+}
+
+impl Matcher {
+    fn matches(&self, segment: &Segment) -> bool {
+        match (self, segment) {
+            (Matcher::Any, _) => true,
+            (Matcher::Key(expected), Segment::Key(key)) => expected == key,
+            (Matcher::Index(expected), Segment::Index(index)) => expected == index,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    // `..` matches the step at any depth below the previous one
+    descendant: bool,
+    matcher: Matcher,
+}
+
+// a JSONPath-like selector supporting `.key`, `.*`, `..key`, `[n]`, `[*]`
+// and `["key"]` steps after the `$` root
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+impl Selector {
+    pub fn matches(&self, path: &[Segment]) -> bool {
+        fn matches(steps: &[Step], path: &[Segment]) -> bool {
+            let Some((step, rest)) = steps.split_first() else {
+                return path.is_empty();
+            };
+            if step.descendant {
+                (0..path.len())
+                    .any(|i| step.matcher.matches(&path[i]) && matches(rest, &path[i + 1..]))
+            } else {
+                !path.is_empty() && step.matcher.matches(&path[0]) && matches(rest, &path[1..])
+            }
+        }
+        matches(&self.steps, path)
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s.chars().collect::<Vec<_>>();
+        if chars.first() != Some(&'$') {
+            return Err(SelectorError::MissingRoot);
+        }
+        let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let mut steps = vec![];
+        let mut i = 1;
+        while i < chars.len() {
+            let descendant = chars[i..].starts_with(&['.', '.']);
+            let matcher = if chars[i] == '.' {
+                i += if descendant { 2 } else { 1 };
+                let start = i;
+                if chars.get(i) == Some(&'*') {
+                    i += 1;
+                    Matcher::Any
+                } else {
+                    while i < chars.len() && is_name(chars[i]) {
+                        i += 1;
+                    }
+                    if i == start {
+                        return Err(SelectorError::UnexpectedCharacter(i));
+                    }
+                    Matcher::Key(chars[start..i].iter().collect())
+                }
+            } else if chars[i] == '[' {
+                let start = i;
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|offset| i + offset)
+                    .ok_or(SelectorError::UnterminatedBracket(start))?;
+                let inner = chars[i + 1..end].iter().collect::<String>();
+                i = end + 1;
+                if inner == "*" {
+                    Matcher::Any
+                } else if let Ok(index) = inner.parse() {
+                    Matcher::Index(index)
+                } else if let Ok(Value::String(key)) = serde_json::from_str(&inner) {
+                    Matcher::Key(key)
+                } else {
+                    return Err(SelectorError::UnexpectedCharacter(start + 1));
+                }
+            } else {
+                return Err(SelectorError::UnexpectedCharacter(i));
+            };
+            steps.push(Step {
+                descendant,
+                matcher,
+            });
+        }
+        Ok(Selector { steps })
+    }
+}
+
+// a sum that stays exact while only integers are added, and becomes a float
+// once a number with a fraction (or an overflowing sum) appears
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Amount {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Amount {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Amount::Integer(n) => n as f64,
+            Amount::Float(x) => x,
+        }
+    }
+}
+
+impl Default for Amount {
+    fn default() -> Self {
+        Amount::Integer(0)
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, other: Amount) {
+        *self = match (*self, other) {
+            (Amount::Integer(a), Amount::Integer(b)) => match a.checked_add(b) {
+                Some(sum) => Amount::Integer(sum),
+                None => Amount::Float(a as f64 + b as f64),
+            },
+            (a, b) => Amount::Float(a.as_f64() + b.as_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Amount::Integer(n) => write!(f, "{n}"),
+            Amount::Float(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+// sum of the numbers that passed the filter, and how much each subtree
+// (named by its path, cut to the breakdown depth) contributed
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub total: Amount,
+    pub by_path: BTreeMap<String, Amount>,
+}
+
+impl Report {
+    fn absorb(&mut self, other: Report) {
+        self.total += other.total;
+        for (path, amount) in other.by_path {
+            *self.by_path.entry(path).or_default() += amount;
+        }
+    }
+}
+
+type MemberPredicate = Box<dyn Fn(&str, &Scalar) -> bool>;
+
+pub struct Filter {
+    member_predicates: Vec<MemberPredicate>,
+    selectors: Vec<Selector>,
+    breakdown_depth: usize,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Filter {
+            member_predicates: vec![],
+            selectors: vec![],
+            breakdown_depth: 1,
+        }
+    }
+
+    // skips any object with a member whose key and scalar value satisfy `predicate`
+    pub fn exclude_objects_where(
+        mut self,
+        predicate: impl Fn(&str, &Scalar) -> bool + 'static,
+    ) -> Self {
+        self.member_predicates.push(Box::new(predicate));
+        self
+    }
+
+    pub fn exclude_objects_with_value(self, value: &str) -> Self {
+        let value = Scalar::String(value.to_owned());
+        self.exclude_objects_where(move |_, v| *v == value)
+    }
+
+    pub fn exclude_objects_with_member(self, key: &str, value: Scalar) -> Self {
+        let key = key.to_owned();
+        self.exclude_objects_where(move |k, v| k == key && *v == value)
+    }
+
+    // skips every value whose path matches `selector`
+    pub fn exclude_selected(mut self, selector: Selector) -> Self {
+        self.selectors.push(selector);
+        self
+    }
+
+    pub fn breakdown_depth(mut self, depth: usize) -> Self {
+        self.breakdown_depth = depth;
+        self
+    }
+
+    pub fn sum_value(&self, json: &Value) -> Report {
+        let mut path = vec![];
+        let node = Node {
+            filter: self,
+            path: &mut path,
+        };
+        node.deserialize(json)
+            .expect("a JSON value is always well-formed")
+            .report
+    }
+
+    pub fn sum_reader<R: Read>(&self, reader: R) -> Result<Report, serde_json::Error> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let mut path = vec![];
+        let node = Node {
+            filter: self,
+            path: &mut path,
+        };
+        let visited = node.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(visited.report)
+    }
+}
+
+#[derive(Default)]
+struct Visited {
+    report: Report,
+    // the value itself when it is not an object or array
+    scalar: Option<Scalar>,
+}
+
+// walks one value during deserialization without building it in memory
+struct Node<'a> {
+    filter: &'a Filter,
+    path: &'a mut Vec<Segment>,
+}
+
+impl Node<'_> {
+    fn number(&self, n: Amount) -> Visited {
+        let depth = self.path.len().min(self.filter.breakdown_depth);
+        Visited {
+            report: Report {
+                total: n,
+                by_path: BTreeMap::from([(format_path(&self.path[..depth]), n)]),
+            },
+            scalar: Some(Scalar::Number(n.as_f64())),
+        }
+    }
+
+    fn scalar(scalar: Scalar) -> Visited {
+        Visited {
+            report: Report::default(),
+            scalar: Some(scalar),
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Node<'_> {
+    type Value = Visited;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Visited, D::Error> {
+        if self
+            .filter
+            .selectors
+            .iter()
+            .any(|selector| selector.matches(self.path))
+        {
+            IgnoredAny::deserialize(deserializer)?;
+            return Ok(Visited::default());
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Node<'_> {
+    type Value = Visited;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Visited, E> {
+        Ok(Node::scalar(Scalar::Null))
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Visited, E> {
+        Ok(Node::scalar(Scalar::Bool(b)))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Visited, E> {
+        Ok(self.number(Amount::Integer(n as i128)))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Visited, E> {
+        Ok(self.number(Amount::Integer(n as i128)))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Visited, E> {
+        Ok(self.number(Amount::Float(n)))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Visited, E> {
+        Ok(Node::scalar(Scalar::String(s.to_owned())))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Visited, A::Error> {
+        let mut report = Report::default();
+        for index in 0.. {
+            self.path.push(Segment::Index(index));
+            let element = seq.next_element_seed(Node {
+                filter: self.filter,
+                path: &mut *self.path,
+            });
+            self.path.pop();
+            match element? {
+                Some(visited) => report.absorb(visited.report),
+                None => break,
+            }
+        }
+        Ok(Visited {
+            report,
+            scalar: None,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Visited, A::Error> {
+        let mut report = Report::default();
+        let mut excluded = false;
+        while let Some(key) = map.next_key::<String>()? {
+            // the rest of an excluded object only needs to be skipped
+            if excluded {
+                map.next_value::<IgnoredAny>()?;
+                continue;
+            }
+            self.path.push(Segment::Key(key));
+            let value = map.next_value_seed(Node {
+                filter: self.filter,
+                path: &mut *self.path,
+            });
+            let Some(Segment::Key(key)) = self.path.pop() else {
+                unreachable!()
+            };
+            let visited = value?;
+            if let Some(scalar) = &visited.scalar {
+                excluded = self
+                    .filter
+                    .member_predicates
+                    .iter()
+                    .any(|predicate| predicate(&key, scalar));
+            }
+            report.absorb(visited.report);
+        }
+        if excluded {
+            report = Report::default();
+        }
+        Ok(Visited {
+            report,
+            scalar: None,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,4 +526,134 @@ mod tests {
         let json: Value = serde_json::from_str(string).unwrap();
         assert!(has_red(&json));
     }
+
+    const PEOPLE: &str = r#"{
+        "name": "Jimmy",
+        "age": 30,
+        "weight": 70.5,
+        "numbers": [1, 2, 3, 4.25],
+        "phones": {"home": 10, "work": 20, "color": "red"},
+        "pets": [{"kind": "cat", "age": 3}, {"kind": "dog", "age": 5, "color": "red"}],
+        "odd key": {"a": {"b": 1}}
+    }"#;
+
+    #[test]
+    fn filter_sum_value_test_1() {
+        // examples from the puzzle description
+        let filter = Filter::new().exclude_objects_with_value("red");
+        for (string, sum) in [
+            (r#"[1,2,3]"#, 6),
+            (r#"[1,{"c":"red","b":2},3]"#, 4),
+            (r#"{"d":"red","e":[1,2,3,4],"f":5}"#, 0),
+            (r#"[1,"red",5]"#, 6),
+        ] {
+            let json: Value = serde_json::from_str(string).unwrap();
+            assert_eq!(filter.sum_value(&json).total, Amount::Integer(sum));
+            assert_eq!(
+                filter.sum_reader(string.as_bytes()).unwrap().total,
+                Amount::Integer(sum)
+            );
+        }
+    }
+
+    #[test]
+    fn filter_sum_value_test_2() {
+        // beyond 2^53, where a float sum would round
+        let json = serde_json::json!([9007199254740993_i64, 1, -3, u64::MAX]);
+        let report = Filter::new().sum_value(&json);
+        assert_eq!(
+            report.total,
+            Amount::Integer(9007199254740991 + u64::MAX as i128)
+        );
+        assert_eq!(report.total.to_string(), "18455751272964292606");
+        let mut total = report.total;
+        total += Amount::Float(0.5);
+        assert!(matches!(total, Amount::Float(_)));
+    }
+
+    #[test]
+    fn filter_sum_reader_test_1() {
+        let report = Filter::new()
+            .exclude_objects_with_value("red")
+            .sum_reader(PEOPLE.as_bytes())
+            .unwrap();
+        assert_eq!(report.total, Amount::Float(30.0 + 70.5 + 10.25 + 3.0 + 1.0));
+        let by_path = report
+            .by_path
+            .iter()
+            .map(|(path, amount)| (path.as_str(), *amount))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            by_path,
+            [
+                ("$.age", Amount::Integer(30)),
+                ("$.numbers", Amount::Float(10.25)),
+                ("$.pets", Amount::Integer(3)),
+                ("$.weight", Amount::Float(70.5)),
+                ("$[\"odd key\"]", Amount::Integer(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_sum_reader_test_2() {
+        let filter = Filter::new()
+            .exclude_objects_with_member("kind", Scalar::String("cat".to_owned()))
+            .exclude_objects_where(|key, value| key == "work" && *value == Scalar::Number(20.0))
+            .breakdown_depth(2);
+        let report = filter.sum_reader(PEOPLE.as_bytes()).unwrap();
+        assert_eq!(report.total, Amount::Float(30.0 + 70.5 + 10.25 + 5.0 + 1.0));
+        assert_eq!(report.by_path["$.pets[1]"], Amount::Integer(5));
+        assert_eq!(report.by_path["$.numbers[3]"], Amount::Float(4.25));
+        assert_eq!(report.by_path["$[\"odd key\"].a"], Amount::Integer(1));
+        assert!(!report.by_path.contains_key("$.phones"));
+    }
+
+    #[test]
+    fn filter_sum_reader_test_3() {
+        let filter = Filter::new()
+            .exclude_selected("$.numbers[*]".parse().unwrap())
+            .exclude_selected("$..age".parse().unwrap())
+            .exclude_selected("$[\"odd key\"]".parse().unwrap())
+            .breakdown_depth(0);
+        let report = filter.sum_reader(PEOPLE.as_bytes()).unwrap();
+        assert_eq!(report.total, Amount::Float(70.5 + 30.0));
+        assert_eq!(
+            report.by_path,
+            BTreeMap::from([("$".to_owned(), Amount::Float(100.5))])
+        );
+        assert!(filter.sum_reader(&b"[1, 2"[..]).is_err());
+        assert!(filter.sum_reader(&b"[1] 2"[..]).is_err());
+    }
+
+    #[test]
+    fn selector_from_str_test_1() {
+        let selector = "$..pets[0].*".parse::<Selector>().unwrap();
+        let path = |segments: &[&str]| {
+            segments
+                .iter()
+                .map(|s| match s.parse() {
+                    Ok(index) => Segment::Index(index),
+                    Err(_) => Segment::Key(s.to_string()),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(selector.matches(&path(&["pets", "0", "age"])));
+        assert!(selector.matches(&path(&["a", "b", "pets", "0", "kind"])));
+        assert!(!selector.matches(&path(&["pets", "1", "age"])));
+        assert!(!selector.matches(&path(&["pets", "0"])));
+        assert_eq!("pets".parse::<Selector>(), Err(SelectorError::MissingRoot));
+        assert_eq!(
+            "$.".parse::<Selector>(),
+            Err(SelectorError::UnexpectedCharacter(2))
+        );
+        assert_eq!(
+            "$[1".parse::<Selector>(),
+            Err(SelectorError::UnterminatedBracket(1))
+        );
+        assert_eq!(
+            "$[x]".parse::<Selector>(),
+            Err(SelectorError::UnexpectedCharacter(2))
+        );
+    }
 }
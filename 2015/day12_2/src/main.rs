@@ -1,9 +1,14 @@
-use day12_2::count_numbers;
-use serde_json::{from_str, Value};
-use std::fs::read_to_string;
+use day12_2::Filter;
+use std::{fs::File, io::BufReader};
 
 fn main() {
-    let input = read_to_string("input.txt").expect("no input.txt in current directory");
-    let json: Value = from_str(input.trim()).unwrap();
-    println!("Total sum: {}", count_numbers(&json).unwrap());
+    let input = File::open("input.txt").expect("no input.txt in current directory");
+    let report = Filter::new()
+        .exclude_objects_with_value("red")
+        .sum_reader(BufReader::new(input))
+        .unwrap();
+    for (path, amount) in &report.by_path {
+        println!("{path}: {amount}");
+    }
+    println!("Total sum: {}", report.total);
 }
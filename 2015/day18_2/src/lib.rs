@@ -1,5 +1,254 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::str::FromStr;
+
 use itertools::Itertools;
 use ndarray::{Array2, ShapeError};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseRuleError {
+    #[error("rule must look like B<digits>/S<digits>")]
+    InvalidFormat,
+    #[error("invalid neighbor count: {0}")]
+    InvalidCount(char),
+}
+
+// birth and survival conditions indexed by the number of live neighbors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    birth: [bool; 9],
+    survive: [bool; 9],
+}
+
+impl Rule {
+    pub fn conway() -> Self {
+        "B3/S23".parse().unwrap()
+    }
+
+    pub fn next_state(&self, alive: bool, neighbors: usize) -> bool {
+        if alive {
+            self.survive[neighbors]
+        } else {
+            self.birth[neighbors]
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (birth, survive) = s.split_once('/').ok_or(ParseRuleError::InvalidFormat)?;
+        let birth = birth
+            .strip_prefix('B')
+            .ok_or(ParseRuleError::InvalidFormat)?;
+        let survive = survive
+            .strip_prefix('S')
+            .ok_or(ParseRuleError::InvalidFormat)?;
+        let counts = |digits: &str| {
+            let mut counts = [false; 9];
+            for c in digits.chars() {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => counts[n as usize] = true,
+                    _ => return Err(ParseRuleError::InvalidCount(c)),
+                }
+            }
+            Ok(counts)
+        };
+        Ok(Rule {
+            birth: counts(birth)?,
+            survive: counts(survive)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    // cells outside the grid are always dead
+    Dead,
+    // the grid wraps around at its edges
+    Toroidal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    // first generation of the repeating sequence
+    pub start: usize,
+    pub period: usize,
+}
+
+// runs `step` from `initial` until a state repeats, giving up after `limit`
+// generations
+pub fn find_cycle<S, F>(initial: S, limit: usize, mut step: F) -> Option<Cycle>
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let mut seen = HashMap::from([(initial.clone(), 0)]);
+    let mut state = initial;
+    for generation in 1..=limit {
+        state = step(&state);
+        if let Some(&start) = seen.get(&state) {
+            return Some(Cycle {
+                start,
+                period: generation - start,
+            });
+        }
+        seen.insert(state.clone(), generation);
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Automaton {
+    rule: Rule,
+    boundary: Boundary,
+    stuck: Option<Array2<bool>>,
+}
+
+impl Automaton {
+    pub fn new(rule: Rule, boundary: Boundary) -> Self {
+        Automaton {
+            rule,
+            boundary,
+            stuck: None,
+        }
+    }
+
+    // cells set in `mask` are forced on after every step
+    pub fn with_stuck(mut self, mask: Array2<bool>) -> Self {
+        self.stuck = Some(mask);
+        self
+    }
+
+    fn neighbors(&self, grid: &Array2<bool>, row: usize, col: usize) -> usize {
+        let (rows, cols) = grid.dim();
+        (-1..=1i64)
+            .cartesian_product(-1..=1i64)
+            .filter(|&offset| offset != (0, 0))
+            .filter(|&(dr, dc)| {
+                let (r, c) = (row as i64 + dr, col as i64 + dc);
+                match self.boundary {
+                    Boundary::Dead => {
+                        r >= 0
+                            && c >= 0
+                            && r < rows as i64
+                            && c < cols as i64
+                            && grid[(r as usize, c as usize)]
+                    }
+                    Boundary::Toroidal => {
+                        grid[(
+                            r.rem_euclid(rows as i64) as usize,
+                            c.rem_euclid(cols as i64) as usize,
+                        )]
+                    }
+                }
+            })
+            .count()
+    }
+
+    pub fn step(&self, grid: &Array2<bool>) -> Array2<bool> {
+        let mut next = Array2::from_shape_fn(grid.raw_dim(), |(row, col)| {
+            self.rule
+                .next_state(grid[(row, col)], self.neighbors(grid, row, col))
+        });
+        if let Some(stuck) = &self.stuck {
+            next.zip_mut_with(stuck, |cell, &stuck| *cell |= stuck);
+        }
+        next
+    }
+
+    pub fn run(&self, grid: &Array2<bool>, generations: usize) -> Array2<bool> {
+        (0..generations).fold(grid.clone(), |grid, _| self.step(&grid))
+    }
+
+    pub fn find_cycle(&self, grid: &Array2<bool>, limit: usize) -> Option<Cycle> {
+        find_cycle(grid.clone(), limit, |grid| self.step(grid))
+    }
+}
+
+// live cells on an unbounded plane; only cells next to a live cell are
+// considered for birth, so rules containing B0 behave as if it were absent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseLife {
+    rule: Rule,
+    cells: HashSet<(i64, i64)>,
+}
+
+impl SparseLife {
+    pub fn new(rule: Rule, cells: impl IntoIterator<Item = (i64, i64)>) -> Self {
+        SparseLife {
+            rule,
+            cells: cells.into_iter().collect(),
+        }
+    }
+
+    pub fn from_matrix(rule: Rule, matrix: &Array2<bool>) -> Self {
+        let cells = matrix
+            .indexed_iter()
+            .filter(|(_, &alive)| alive)
+            .map(|((row, col), _)| (row as i64, col as i64));
+        Self::new(rule, cells)
+    }
+
+    pub fn cells(&self) -> &HashSet<(i64, i64)> {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn step(&self) -> SparseLife {
+        let mut counts = HashMap::<(i64, i64), usize>::new();
+        for &(row, col) in &self.cells {
+            for (dr, dc) in (-1..=1).cartesian_product(-1..=1) {
+                if (dr, dc) != (0, 0) {
+                    *counts.entry((row + dr, col + dc)).or_default() += 1;
+                }
+            }
+        }
+        // isolated live cells have no entry in `counts`
+        let isolated = self
+            .cells
+            .iter()
+            .filter(|cell| self.rule.survive[0] && !counts.contains_key(cell))
+            .copied()
+            .collect::<Vec<_>>();
+        let cells = counts
+            .into_iter()
+            .filter(|(cell, neighbors)| self.rule.next_state(self.cells.contains(cell), *neighbors))
+            .map(|(cell, _)| cell)
+            .chain(isolated)
+            .collect();
+        SparseLife {
+            rule: self.rule,
+            cells,
+        }
+    }
+
+    // cycles are detected on the exact set of cells, so moving patterns
+    // such as gliders never repeat
+    pub fn find_cycle(&self, limit: usize) -> Option<Cycle> {
+        let sorted = |life: SparseLife| life.cells.into_iter().sorted().collect::<Vec<_>>();
+        find_cycle(sorted(self.clone()), limit, |cells| {
+            sorted(SparseLife::new(self.rule, cells.iter().copied()).step())
+        })
+    }
+}
+
+pub fn corner_mask(rows: usize, cols: usize) -> Array2<bool> {
+    let mut mask = Array2::default((rows, cols));
+    for corner in [(0, 0), (0, cols - 1), (rows - 1, 0), (rows - 1, cols - 1)] {
+        mask[corner] = true;
+    }
+    mask
+}
 
 pub fn parse_matrix(lines: &[&str]) -> Result<Array2<bool>, ShapeError> {
     let dim = lines.len();
@@ -26,38 +275,10 @@ pub fn parse_matrix(lines: &[&str]) -> Result<Array2<bool>, ShapeError> {
 }
 
 pub fn next_step(initial: &Array2<bool>) -> Array2<bool> {
-    let dim = initial.dim();
-    let (rows, cols) = dim;
-    let mut next = Array2::default(initial.raw_dim());
-    for (row, col) in (0..rows).cartesian_product(0..cols) {
-        let neighbors = (row as i64 - 1..=row as i64 + 1)
-            .cartesian_product(col as i64 - 1..=col as i64 + 1)
-            .map(|(r, c)| {
-                if (row as i64 == r && col as i64 == c)
-                    || r < 0
-                    || c < 0
-                    || r >= rows as i64
-                    || c >= cols as i64
-                {
-                    return 0;
-                }
-                if initial[(r as usize, c as usize)] {
-                    1
-                } else {
-                    0
-                }
-            })
-            .sum::<u64>();
-        let already_on = initial[(row, col)];
-        if neighbors == 3 || (already_on && neighbors == 2) {
-            next[(row, col)] = true;
-        }
-    }
-    next[(0, 0)] = true;
-    next[(0, cols - 1)] = true;
-    next[(rows - 1, 0)] = true;
-    next[(rows - 1, cols - 1)] = true;
-    next
+    let (rows, cols) = initial.dim();
+    Automaton::new(Rule::conway(), Boundary::Dead)
+        .with_stuck(corner_mask(rows, cols))
+        .step(initial)
 }
 
 pub fn count_on(matrix: &Array2<bool>) -> u64 {
@@ -112,4 +333,110 @@ mod tests {
             parse_matrix(&[".#.#.#", "...##.", "#....#", "..#...", "#.#..#", "####.."]).unwrap();
         assert_eq!(count_on(&matrix), 17)
     }
+
+    fn grid(lines: &[&str]) -> Array2<bool> {
+        let vec = lines
+            .iter()
+            .flat_map(|line| line.chars().map(|c| c == '#'))
+            .collect::<Vec<_>>();
+        Array2::from_shape_vec([lines.len(), lines[0].len()], vec).unwrap()
+    }
+
+    #[test]
+    fn rule_from_str_test_1() {
+        let rule = "B36/S23".parse::<Rule>().unwrap();
+        assert!(rule.next_state(false, 6));
+        assert!(!rule.next_state(true, 6));
+        assert!(rule.next_state(true, 2));
+        assert_eq!("B3/S".parse::<Rule>().unwrap().survive, [false; 9]);
+        assert_eq!("B3S23".parse::<Rule>(), Err(ParseRuleError::InvalidFormat));
+        assert_eq!(
+            "B39/S23".parse::<Rule>(),
+            Err(ParseRuleError::InvalidCount('9'))
+        );
+    }
+
+    #[test]
+    fn automaton_step_test_1() {
+        // example from part 1, without stuck corners
+        let automaton = Automaton::new(Rule::conway(), Boundary::Dead);
+        let initial = grid(&[".#.#.#", "...##.", "#....#", "..#...", "#.#..#", "####.."]);
+        let after = grid(&["......", "......", "..##..", "..##..", "......", "......"]);
+        assert_eq!(automaton.run(&initial, 4), after);
+        assert_eq!(
+            automaton.find_cycle(&initial, 10),
+            Some(Cycle {
+                start: 4,
+                period: 1
+            })
+        );
+    }
+
+    #[test]
+    fn automaton_step_test_2() {
+        let automaton = Automaton::new(Rule::conway(), Boundary::Toroidal);
+        let glider = grid(&[".#...", "..#..", "###..", ".....", "....."]);
+        let moved = grid(&[".....", "..#..", "...#.", ".###.", "....."]);
+        assert_eq!(automaton.run(&glider, 4), moved);
+        // the glider crosses the 5x5 torus diagonally every 20 generations
+        assert_eq!(
+            automaton.find_cycle(&glider, 100),
+            Some(Cycle {
+                start: 0,
+                period: 20
+            })
+        );
+        let dead = Automaton::new(Rule::conway(), Boundary::Dead);
+        assert_ne!(dead.run(&glider, 20), glider);
+    }
+
+    #[test]
+    fn automaton_step_test_3() {
+        let initial =
+            parse_matrix(&["##.#.#", "...##.", "#....#", "..#...", "#.#..#", "####.#"]).unwrap();
+        let automaton =
+            Automaton::new(Rule::conway(), Boundary::Dead).with_stuck(corner_mask(6, 6));
+        assert_eq!(count_on(&automaton.run(&initial, 5)), 17);
+    }
+
+    #[test]
+    fn sparse_life_step_test_1() {
+        let blinker = SparseLife::new(Rule::conway(), [(0, -1), (0, 0), (0, 1)]);
+        let next = blinker.step();
+        assert_eq!(next.cells(), &HashSet::from([(-1, 0), (0, 0), (1, 0)]));
+        assert_eq!(
+            blinker.find_cycle(10),
+            Some(Cycle {
+                start: 0,
+                period: 2
+            })
+        );
+    }
+
+    #[test]
+    fn sparse_life_step_test_2() {
+        let glider = grid(&[".#.", "..#", "###"]);
+        let life = SparseLife::from_matrix(Rule::conway(), &glider);
+        let moved = (0..4).fold(life.clone(), |life, _| life.step());
+        let shifted = life
+            .cells()
+            .iter()
+            .map(|&(row, col)| (row + 1, col + 1))
+            .collect::<HashSet<_>>();
+        assert_eq!(moved.cells(), &shifted);
+        assert_eq!(life.find_cycle(40), None);
+        assert_eq!(moved.len(), 5);
+    }
+
+    #[test]
+    fn sparse_life_step_test_3() {
+        // matches the dense engine while the pattern stays inside the grid
+        let initial = grid(&["......", ".##...", ".#....", "....#.", "...##.", "......"]);
+        let dense = Automaton::new(Rule::conway(), Boundary::Dead).run(&initial, 3);
+        let sparse = (0..3).fold(
+            SparseLife::from_matrix(Rule::conway(), &initial),
+            |life, _| life.step(),
+        );
+        assert_eq!(sparse, SparseLife::from_matrix(Rule::conway(), &dense));
+    }
 }
@@ -1,5 +1,10 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::fmt::Write;
+use std::str::FromStr;
 
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, PartialEq)]
 pub struct Sides {
     length: u32,
     width: u32,
@@ -25,31 +30,153 @@ impl Sides {
         let smallest = self.smallest_side();
         volume + 2 * (smallest.0 + smallest.1)
     }
+
+    pub fn volume(&self) -> u32 {
+        self.length * self.width * self.height
+    }
+
+    // whether paper, ribbon and volume can be computed without overflowing;
+    // the ribbon is never smaller than the volume
+    fn fits(&self) -> bool {
+        let (l, w, h) = (self.length as u128, self.width as u128, self.height as u128);
+        let (a, b) = self.smallest_side();
+        let (a, b) = (a as u128, b as u128);
+        let paper = 2 * (l * w + l * h + w * h) + a * b;
+        let ribbon = l * w * h + 2 * (a + b);
+        paper <= u32::MAX as u128 && ribbon <= u32::MAX as u128
+    }
 }
 
-#[derive(Debug)]
-pub struct SidesParseErr;
+#[derive(Debug, Error, PartialEq)]
+pub enum SidesParseErr {
+    #[error("invalid side length: {0:?}")]
+    InvalidLength(String),
+    #[error("expected 3 sides, found {0}")]
+    WrongSideCount(usize),
+    #[error("present is too large: {0}")]
+    TooLarge(String),
+}
 
 impl FromStr for Sides {
     type Err = SidesParseErr;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let sides = match s
+        let sides = s
             .split("x")
-            .map(|s| s.parse())
-            .collect::<Result<Vec<_>, ParseIntError>>()
-        {
-            Ok(s) => Ok(s),
-            Err(_) => Err(SidesParseErr),
-        }?;
+            .map(|side| {
+                side.parse()
+                    .map_err(|_| SidesParseErr::InvalidLength(side.to_owned()))
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
         if sides.len() != 3 {
-            return Err(SidesParseErr);
+            return Err(SidesParseErr::WrongSideCount(sides.len()));
         }
-        Ok(Sides {
+        let sides = Sides {
             length: sides[0],
             width: sides[1],
             height: sides[2],
+        };
+        if !sides.fits() {
+            return Err(SidesParseErr::TooLarge(s.to_owned()));
+        }
+        Ok(sides)
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("line #{line_number}: {source}")]
+pub struct ManifestError {
+    line_number: usize,
+    source: SidesParseErr,
+}
+
+// one present per line, blank lines being ignored
+pub fn parse_manifest(input: &str) -> Result<Vec<Sides>, ManifestError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            line.trim().parse().map_err(|source| ManifestError {
+                line_number,
+                source,
+            })
         })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PresentReport {
+    pub length: u32,
+    pub width: u32,
+    pub height: u32,
+    pub paper: u32,
+    pub ribbon: u32,
+    pub volume: u32,
+    pub smallest_side: (u32, u32),
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Totals {
+    pub presents: usize,
+    pub paper: u64,
+    pub ribbon: u64,
+    pub volume: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub presents: Vec<PresentReport>,
+    pub totals: Totals,
+}
+
+impl Report {
+    pub fn new(presents: &[Sides]) -> Report {
+        let presents = presents
+            .iter()
+            .map(|sides| PresentReport {
+                length: sides.length,
+                width: sides.width,
+                height: sides.height,
+                paper: sides.needed_paper(),
+                ribbon: sides.needed_ribbon(),
+                volume: sides.volume(),
+                smallest_side: sides.smallest_side(),
+            })
+            .collect::<Vec<_>>();
+        let totals = Totals {
+            presents: presents.len(),
+            paper: presents.iter().map(|p| p.paper as u64).sum(),
+            ribbon: presents.iter().map(|p| p.ribbon as u64).sum(),
+            volume: presents.iter().map(|p| p.volume as u64).sum(),
+        };
+        Report { presents, totals }
+    }
+
+    // one row per present followed by a row of totals
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("length,width,height,paper,ribbon,volume,smallest_side\n");
+        for p in &self.presents {
+            let (a, b) = p.smallest_side;
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{a}x{b}",
+                p.length, p.width, p.height, p.paper, p.ribbon, p.volume
+            )
+            .unwrap();
+        }
+        let totals = &self.totals;
+        writeln!(
+            csv,
+            "total,,,{},{},{},",
+            totals.paper, totals.ribbon, totals.volume
+        )
+        .unwrap();
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
     }
 }
 
@@ -88,4 +215,88 @@ mod tests {
         let sides: Sides = "8x3x5".parse().unwrap();
         assert_eq!(sides.needed_ribbon(), 136);
     }
+
+    #[test]
+    fn sides_from_str_test_2() {
+        assert_eq!(
+            "12x17".parse::<Sides>().err(),
+            Some(SidesParseErr::WrongSideCount(2))
+        );
+        assert_eq!(
+            "12x-1x3".parse::<Sides>().err(),
+            Some(SidesParseErr::InvalidLength("-1".to_owned()))
+        );
+    }
+
+    #[test]
+    fn sides_from_str_test_3() {
+        assert!("1000x1000x4000".parse::<Sides>().is_ok());
+        assert_eq!(
+            "2000x2000x2000".parse::<Sides>().err(),
+            Some(SidesParseErr::TooLarge("2000x2000x2000".to_owned()))
+        );
+        assert_eq!(
+            parse_manifest("2x3x4\n1x1x4294967295"),
+            Err(ManifestError {
+                line_number: 1,
+                source: SidesParseErr::TooLarge("1x1x4294967295".to_owned())
+            })
+        );
+    }
+
+    #[test]
+    fn parse_manifest_test_1() {
+        let presents = parse_manifest("2x3x4\n\n 1x1x10 \n").unwrap();
+        assert_eq!(presents.len(), 2);
+        assert_eq!(
+            parse_manifest("2x3x4\n\n1x1\n"),
+            Err(ManifestError {
+                line_number: 2,
+                source: SidesParseErr::WrongSideCount(2)
+            })
+        );
+    }
+
+    #[test]
+    fn report_test_1() {
+        // examples from the puzzle description
+        let report = Report::new(&parse_manifest("2x3x4\n1x1x10").unwrap());
+        assert_eq!(
+            report.totals,
+            Totals {
+                presents: 2,
+                paper: 58 + 43,
+                ribbon: 34 + 14,
+                volume: 24 + 10
+            }
+        );
+        assert_eq!(
+            report.to_csv(),
+            "length,width,height,paper,ribbon,volume,smallest_side\n\
+             2,3,4,58,34,24,2x3\n\
+             1,1,10,43,14,10,1x1\n\
+             total,,,101,48,34,\n"
+        );
+    }
+
+    #[test]
+    fn report_test_2() {
+        let report = Report::new(&parse_manifest("2x3x4").unwrap());
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "presents": [{
+                    "length": 2,
+                    "width": 3,
+                    "height": 4,
+                    "paper": 58,
+                    "ribbon": 34,
+                    "volume": 24,
+                    "smallest_side": [2, 3]
+                }],
+                "totals": {"presents": 1, "paper": 58, "ribbon": 34, "volume": 24}
+            })
+        );
+    }
 }
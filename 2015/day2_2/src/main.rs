@@ -1,12 +1,31 @@
-use std::fs::read_to_string;
+use std::{env, fs::read_to_string, process};
 
-use day2_2::Sides;
+use day2_2::{parse_manifest, Report};
+
+const USAGE: &str = "usage: day2_2 [--csv | --json]";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    process::exit(1);
+}
 
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let format = match args.as_slice() {
+        [] => None,
+        [flag] if flag == "--csv" || flag == "--json" => Some(flag.as_str()),
+        _ => fail(&format!("unknown arguments: {}\n{USAGE}", args.join(" "))),
+    };
+
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
-    let total: u32 = input
-        .lines()
-        .map(|l| l.parse::<Sides>().unwrap().needed_ribbon())
-        .sum();
-    println!("{total} feet of ribbon required");
+    let presents = match parse_manifest(&input) {
+        Ok(presents) => presents,
+        Err(err) => fail(&err.to_string()),
+    };
+    let report = Report::new(&presents);
+    match format {
+        Some("--csv") => print!("{}", report.to_csv()),
+        Some(_) => println!("{}", report.to_json()),
+        None => println!("{} feet of ribbon required", report.totals.ribbon),
+    }
 }
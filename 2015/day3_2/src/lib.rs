@@ -1,6 +1,7 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Move {
     Up,
     Right,
@@ -30,26 +31,169 @@ fn next_pos(current: (i64, i64), dir: &Move) -> (i64, i64) {
     }
 }
 
-pub fn count_distinct_houses(moves: &[Move]) -> usize {
-    let mut visited = HashSet::new();
-    let mut santa_pos = (0i64, 0i64);
-    let mut robot_pos = (0i64, 0i64);
-    let mut santa_moves = true;
-    visited.insert(santa_pos);
-    for m in moves {
-        match santa_moves {
-            true => {
-                santa_pos = next_pos(santa_pos, m);
-                visited.insert(santa_pos);
+pub fn parse_streams(s: &str) -> Vec<Vec<Move>> {
+    s.lines().map(parse_moves).collect()
+}
+
+type House = (i64, i64);
+
+// how moves are handed out to the agents
+pub enum Dispatch<'a> {
+    // agent `i % agents` follows the `i`th move
+    RoundRobin { agents: usize, moves: &'a [Move] },
+    // every agent follows its own stream, all of them moving in lockstep
+    Streams(&'a [Vec<Move>]),
+}
+
+impl Dispatch<'_> {
+    fn agents(&self) -> usize {
+        match self {
+            Dispatch::RoundRobin { agents, .. } => *agents,
+            Dispatch::Streams(streams) => streams.len(),
+        }
+    }
+
+    // (agent, move) in the order they happen
+    fn steps(&self) -> Vec<(usize, &Move)> {
+        match *self {
+            Dispatch::RoundRobin { agents: 0, .. } => vec![],
+            Dispatch::RoundRobin { agents, moves } => moves
+                .iter()
+                .enumerate()
+                .map(|(i, m)| (i % agents, m))
+                .collect(),
+            Dispatch::Streams(streams) => {
+                let longest = streams.iter().map(Vec::len).max().unwrap_or(0);
+                (0..longest)
+                    .flat_map(|t| {
+                        streams
+                            .iter()
+                            .enumerate()
+                            .filter_map(move |(agent, stream)| Some((agent, stream.get(t)?)))
+                    })
+                    .collect()
             }
-            false => {
-                robot_pos = next_pos(robot_pos, m);
-                visited.insert(robot_pos);
+        }
+    }
+}
+
+// presents delivered to each house, every agent delivering one at the
+// starting house and one after each move
+#[derive(Debug, PartialEq)]
+pub struct Visits {
+    counts: HashMap<House, u64>,
+    positions: Vec<House>,
+}
+
+const HEAT_LEVELS: &[u8] = b".:-=+*#%@";
+
+impl Visits {
+    pub fn simulate(dispatch: Dispatch) -> Visits {
+        let mut positions = vec![(0, 0); dispatch.agents()];
+        let mut counts = HashMap::new();
+        if !positions.is_empty() {
+            counts.insert((0, 0), positions.len() as u64);
+        }
+        for (agent, m) in dispatch.steps() {
+            positions[agent] = next_pos(positions[agent], m);
+            *counts.entry(positions[agent]).or_default() += 1;
+        }
+        Visits { counts, positions }
+    }
+
+    pub fn distinct_houses(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn count(&self, house: House) -> u64 {
+        self.counts.get(&house).copied().unwrap_or(0)
+    }
+
+    // where each agent ended up
+    pub fn positions(&self) -> &[House] {
+        &self.positions
+    }
+
+    pub fn most_visited(&self) -> Option<(House, u64)> {
+        self.counts
+            .iter()
+            .map(|(&house, &count)| (house, count))
+            .max_by_key(|&((x, y), count)| (count, -y, -x))
+    }
+
+    // (min_x, min_y, max_x, max_y)
+    fn bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        let xs = self.counts.keys().map(|&(x, _)| x);
+        let ys = self.counts.keys().map(|&(_, y)| y);
+        Some((xs.clone().min()?, ys.clone().min()?, xs.max()?, ys.max()?))
+    }
+
+    // position of `count` in 0..levels, the most visited house being at the
+    // top level
+    fn heat(&self, count: u64, levels: usize) -> usize {
+        let max = self.counts.values().copied().max().unwrap_or(1);
+        if max == 1 {
+            return 0;
+        }
+        (count - 1) as usize * (levels - 1) / (max - 1) as usize
+    }
+
+    // one character per house with north at the top, from `.` for a single
+    // present up to `@` near the most visited house
+    pub fn render_ascii(&self) -> String {
+        let Some((min_x, min_y, max_x, max_y)) = self.bounds() else {
+            return String::new();
+        };
+        let mut ascii = String::new();
+        for y in (min_y..=max_y).rev() {
+            for x in min_x..=max_x {
+                ascii.push(match self.count((x, y)) {
+                    0 => ' ',
+                    count => HEAT_LEVELS[self.heat(count, HEAT_LEVELS.len())] as char,
+                });
             }
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    pub fn render_svg(&self, cell_size: u32) -> String {
+        let Some((min_x, min_y, max_x, max_y)) = self.bounds() else {
+            return String::from(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\"></svg>\n",
+            );
+        };
+        let size = cell_size as i64;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+            (max_x - min_x + 1) * size,
+            (max_y - min_y + 1) * size
+        );
+        let houses = self
+            .counts
+            .iter()
+            .map(|(&(x, y), &count)| ((-y, x), count))
+            .collect::<BTreeMap<_, _>>();
+        for ((neg_y, x), count) in houses {
+            // from pale yellow to dark red
+            let heat = self.heat(count, 256) as u32;
+            let (green, blue) = (255 - heat * 3 / 4, 160 * (255 - heat) / 255);
+            writeln!(
+                svg,
+                "  <rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" fill=\"rgb(255,{green},{blue})\"><title>{x},{} {count}</title></rect>",
+                (x - min_x) * size,
+                (max_y + neg_y) * size,
+                -neg_y
+            )
+            .unwrap();
         }
-        santa_moves = !santa_moves;
+        svg.push_str("</svg>\n");
+        svg
     }
-    visited.len()
+}
+
+pub fn count_distinct_houses(moves: &[Move]) -> usize {
+    Visits::simulate(Dispatch::RoundRobin { agents: 2, moves }).distinct_houses()
 }
 
 #[cfg(test)]
@@ -66,4 +210,76 @@ mod tests {
     fn count_distinct_houses_test_1() {
         assert_eq!(count_distinct_houses(&parse_moves("^>v<>")), 3);
     }
+
+    #[test]
+    fn count_distinct_houses_test_2() {
+        // examples from the puzzle description
+        assert_eq!(count_distinct_houses(&parse_moves("^v")), 3);
+        assert_eq!(count_distinct_houses(&parse_moves("^v^v^v^v^v")), 11);
+    }
+
+    #[test]
+    fn visits_simulate_test_1() {
+        let moves = parse_moves("^>v<");
+        let santa = Visits::simulate(Dispatch::RoundRobin {
+            agents: 1,
+            moves: &moves,
+        });
+        assert_eq!(santa.distinct_houses(), 4);
+        assert_eq!(santa.count((0, 0)), 2);
+        assert_eq!(santa.most_visited(), Some(((0, 0), 2)));
+        let moves = parse_moves("^^^>>>vvv");
+        let trio = Visits::simulate(Dispatch::RoundRobin {
+            agents: 3,
+            moves: &moves,
+        });
+        assert_eq!(trio.positions(), [(1, 0), (1, 0), (1, 0)]);
+        assert_eq!(trio.count((0, 0)), 3);
+        assert_eq!(trio.count((1, 1)), 3);
+        assert_eq!(trio.distinct_houses(), 4);
+        let nobody = Visits::simulate(Dispatch::RoundRobin {
+            agents: 0,
+            moves: &moves,
+        });
+        assert_eq!(nobody.distinct_houses(), 0);
+    }
+
+    #[test]
+    fn visits_simulate_test_2() {
+        let streams = parse_streams("^^\n>\nvv<");
+        let visits = Visits::simulate(Dispatch::Streams(&streams));
+        assert_eq!(visits.positions(), [(0, 2), (1, 0), (-1, -2)]);
+        assert_eq!(visits.distinct_houses(), 7);
+        // two agents following interleaved streams match round-robin
+        let moves = parse_moves("^>v<>>^^<v");
+        let santa = moves.iter().copied().step_by(2).collect();
+        let robot = moves.iter().copied().skip(1).step_by(2).collect();
+        assert_eq!(
+            Visits::simulate(Dispatch::Streams(&[santa, robot])),
+            Visits::simulate(Dispatch::RoundRobin {
+                agents: 2,
+                moves: &moves
+            })
+        );
+    }
+
+    #[test]
+    fn visits_render_test_1() {
+        let moves = parse_moves("^>v<^>v<>");
+        let visits = Visits::simulate(Dispatch::RoundRobin {
+            agents: 1,
+            moves: &moves,
+        });
+        assert_eq!(visits.render_ascii(), "++\n@@\n");
+        let svg = visits.render_svg(10);
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"20\">\n"
+        ));
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert!(svg.contains(
+            "<rect x=\"0\" y=\"10\" width=\"10\" height=\"10\" fill=\"rgb(255,64,0)\"><title>0,0 3</title></rect>"
+        ));
+        let nobody = Visits::simulate(Dispatch::Streams(&[]));
+        assert_eq!(nobody.render_ascii(), "");
+    }
 }
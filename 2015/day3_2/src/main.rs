@@ -1,10 +1,56 @@
-use day3_2::{count_distinct_houses, parse_moves};
-use std::fs::read_to_string;
+use day3_2::{parse_moves, parse_streams, Dispatch, Visits};
+use std::{env, fs::read_to_string, process};
+
+const USAGE: &str = "usage: day3_2 [--agents N | --streams] [--ascii | --svg]";
+
+enum Render {
+    Ascii,
+    Svg,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}\n{USAGE}");
+    process::exit(1);
+}
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
-    println!(
-        "{} houses visited",
-        count_distinct_houses(&parse_moves(&input))
-    );
+
+    // two agents taking turns, as in the puzzle, unless told otherwise;
+    // with --streams every line of the input is the route of one agent
+    let mut agents = 2;
+    let mut streams = false;
+    let mut render = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--agents" => {
+                agents = match args.next().map(|n| n.parse()) {
+                    Some(Ok(n)) => n,
+                    _ => fail("--agents expects a number"),
+                }
+            }
+            "--streams" => streams = true,
+            "--ascii" => render = Some(Render::Ascii),
+            "--svg" => render = Some(Render::Svg),
+            _ => fail(&format!("unknown argument: {arg}")),
+        }
+    }
+
+    let moves = parse_moves(&input);
+    let routes = parse_streams(&input);
+    let visits = Visits::simulate(if streams {
+        Dispatch::Streams(&routes)
+    } else {
+        Dispatch::RoundRobin {
+            agents,
+            moves: &moves,
+        }
+    });
+    // a rendering is printed on its own so that it can be redirected to a file
+    match render {
+        Some(Render::Ascii) => print!("{}", visits.render_ascii()),
+        Some(Render::Svg) => print!("{}", visits.render_svg(4)),
+        None => println!("{} houses visited", visits.distinct_houses()),
+    }
 }
@@ -1,18 +1,11 @@
-fn starts_with_5_zeros(key: &str, suffix: u64) -> bool {
-    let mut combined = key.to_owned();
-    combined.push_str(&suffix.to_string());
-    let hash = md5::compute(combined);
-    &format!("{:x}", hash)[0..5] == "00000"
-}
+use hash_mining::Miner;
 
 pub fn find_lowest_suffix(key: &str) -> u64 {
-    let mut suffix: u64 = 1;
-    loop {
-        if starts_with_5_zeros(key, suffix) {
-            return suffix;
-        }
-        suffix += 1;
-    }
+    Miner::new(key, 5)
+        .starting_at(1)
+        .next()
+        .expect("nonce space exhausted")
+        .nonce
 }
 
 #[cfg(test)]
@@ -20,12 +13,12 @@ mod tests {
     use super::*;
 
     #[test]
-    fn starts_with_5_zeros_test_1() {
-        assert!(starts_with_5_zeros("pqrstuv", 1048970));
+    fn find_lowest_suffix_test_1() {
+        assert_eq!(find_lowest_suffix("abcdef"), 609043);
     }
 
     #[test]
-    fn starts_with_5_zeros_test_2() {
-        assert!(!starts_with_5_zeros("abcdefg", 234534));
+    fn find_lowest_suffix_test_2() {
+        assert_eq!(find_lowest_suffix("pqrstuv"), 1048970);
     }
 }
@@ -1,18 +1,15 @@
-fn starts_with_6_zeros(key: &str, suffix: u64) -> bool {
-    let mut combined = key.to_owned();
-    combined.push_str(&suffix.to_string());
-    let hash = md5::compute(combined);
-    &format!("{:x}", hash)[0..6] == "000000"
-}
+use hash_mining::Miner;
 
 pub fn find_lowest_suffix(key: &str) -> u64 {
-    let mut suffix: u64 = 1;
-    loop {
-        if starts_with_6_zeros(key, suffix) {
-            return suffix;
-        }
-        suffix += 1;
-    }
+    find_lowest_suffix_with_zeros(key, 6)
+}
+
+pub fn find_lowest_suffix_with_zeros(key: &str, zeros: usize) -> u64 {
+    Miner::new(key, zeros)
+        .starting_at(1)
+        .next()
+        .expect("nonce space exhausted")
+        .nonce
 }
 
 #[cfg(test)]
@@ -20,7 +17,8 @@ mod tests {
     use super::*;
 
     #[test]
-    fn starts_with_5_zeros_test_1() {
-        assert!(!starts_with_6_zeros("abcdefg", 234534));
+    fn find_lowest_suffix_with_zeros_test_1() {
+        assert_eq!(find_lowest_suffix_with_zeros("abcdef", 5), 609043);
+        assert_eq!(find_lowest_suffix_with_zeros("abcdef", 3), 3337);
     }
 }
@@ -1,46 +1,68 @@
-fn contains_double_pair(s: &str) -> bool {
-    let pairs = s.chars().zip(s.chars().skip(1));
-    let first_pairs = pairs.clone();
-    for (i, pair) in first_pairs.enumerate() {
-        let mut other_pairs = pairs.clone().skip(i + 2);
-        if other_pairs.any(|other| other == pair) {
-            return true;
-        }
-    }
-    false
-}
+use std::sync::LazyLock;
 
-fn contains_sandwich(s: &str) -> bool {
-    s.chars()
-        .zip(s.chars().skip(2))
-        .any(|pair| pair.0 == pair.1)
-}
+use nice_rules::{RuleError, Validator};
+
+pub const PART_1_RULES: &str = "vowels>=3, repeat(gap=0), !contains(ab|cd|pq|xy)";
+pub const PART_2_RULES: &str = "pair_twice, repeat(gap=1)";
+
+static PART_2_VALIDATOR: LazyLock<Validator> =
+    LazyLock::new(|| PART_2_RULES.parse().expect("part 2 rules should parse"));
 
 pub fn is_nice(s: &str) -> bool {
-    contains_double_pair(s) && contains_sandwich(s)
+    PART_2_VALIDATOR.is_valid(s)
+}
+
+pub fn count_nice<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+    rules: &str,
+) -> Result<usize, RuleError> {
+    let validator = rules.parse::<Validator>()?;
+    Ok(lines
+        .into_iter()
+        .filter(|line| validator.is_valid(line))
+        .count())
 }
 
 #[cfg(test)]
 mod tests {
+    use nice_rules::Rule;
+
     use super::*;
 
     #[test]
     fn contains_double_pair_test_1() {
-        assert!(contains_double_pair("abxab"));
+        assert!(Rule::PairTwice.matches("abxab"));
     }
 
     #[test]
     fn contains_double_pair_test_2() {
-        assert!(!contains_double_pair("svaaaunotgbwrtuvnb"));
+        assert!(!Rule::PairTwice.matches("svaaaunotgbwrtuvnb"));
     }
 
     #[test]
     fn contains_sandwich_test_1() {
-        assert!(contains_sandwich("vsdfonouvfgjndghf"));
+        assert!(Rule::Repeat { gap: 1 }.matches("vsdfonouvfgjndghf"));
     }
 
     #[test]
     fn contains_sandwich_test_2() {
-        assert!(!contains_sandwich("bfgnsuinrfguerbv"));
+        assert!(!Rule::Repeat { gap: 1 }.matches("bfgnsuinrfguerbv"));
+    }
+
+    #[test]
+    fn is_nice_test_1() {
+        // examples from the puzzle description
+        assert!(is_nice("qjhvhtzxzqqjkmpb"));
+        assert!(is_nice("xxyxx"));
+        assert!(!is_nice("uurcxstgmygtbstg"));
+        assert!(!is_nice("ieodomkazucvgmuy"));
+    }
+
+    #[test]
+    fn count_nice_test_1() {
+        let lines = ["ugknbfddgicrmopn", "aaa", "jchzalrnumimnmhp", "xxyxx"];
+        assert_eq!(count_nice(lines, PART_1_RULES), Ok(2));
+        assert_eq!(count_nice(lines, PART_2_RULES), Ok(1));
+        assert!(count_nice(lines, "vowels>=3, sparkly").is_err());
     }
 }
@@ -1,8 +1,16 @@
-use day5_2::is_nice;
-use std::fs::read_to_string;
+use day5_2::{count_nice, PART_2_RULES};
+use std::{env, fs::read_to_string, process};
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
-    let count = input.lines().filter(|s| is_nice(s)).count();
+    // a different rule set can be given as the first argument
+    let rules = env::args().nth(1).unwrap_or(PART_2_RULES.to_owned());
+    let count = match count_nice(input.lines(), &rules) {
+        Ok(count) => count,
+        Err(err) => {
+            eprintln!("invalid rules: {err}");
+            process::exit(1);
+        }
+    };
     println!("{count} nice strings");
 }
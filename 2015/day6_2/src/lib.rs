@@ -1,5 +1,6 @@
 use sscanf::sscanf;
 use std::cmp::{max, min};
+use std::io::Write;
 
 #[derive(Debug, PartialEq, Clone)]
 struct Point {
@@ -20,65 +21,254 @@ enum GridAction {
     Toggle(Rect),
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Action {
+    On,
+    Off,
+    Toggle,
+}
+
+pub trait LightModel {
+    type State: Copy + PartialEq + Default + std::fmt::Debug;
+
+    fn apply(action: Action, state: Self::State) -> Self::State;
+    fn brightness(state: Self::State) -> u64;
+}
+
+// part 1: lights are either on or off
+#[derive(Debug, PartialEq)]
+pub struct Binary;
+
+impl LightModel for Binary {
+    type State = bool;
+
+    fn apply(action: Action, state: bool) -> bool {
+        match action {
+            Action::On => true,
+            Action::Off => false,
+            Action::Toggle => !state,
+        }
+    }
+
+    fn brightness(state: bool) -> u64 {
+        state as u64
+    }
+}
+
+// part 2: lights have a brightness that never drops below zero
 #[derive(Debug, PartialEq)]
-pub struct Grid {
-    values: Vec<u64>,
+pub struct Brightness;
+
+impl LightModel for Brightness {
+    type State = u64;
+
+    fn apply(action: Action, state: u64) -> u64 {
+        match action {
+            Action::On => state + 1,
+            Action::Off => state.saturating_sub(1),
+            Action::Toggle => state + 2,
+        }
+    }
+
+    fn brightness(state: u64) -> u64 {
+        state
+    }
 }
 
-impl Grid {
+// the grid is stored as blocks of identical lights: `xs` and `ys` hold the
+// first coordinate of every block column and row, so memory only depends on
+// the number of distinct rectangle edges and not on the grid size
+#[derive(Debug, PartialEq)]
+pub struct Grid<M: LightModel = Brightness> {
+    width: usize,
+    height: usize,
+    xs: Vec<usize>,
+    ys: Vec<usize>,
+    blocks: Vec<Vec<M::State>>,
+}
+
+impl Grid<Brightness> {
     const GRID_SIZE: usize = 1000;
 
     pub fn new() -> Self {
+        Grid::with_size(Grid::GRID_SIZE, Grid::GRID_SIZE)
+    }
+
+    pub fn from_commands(commands: &[&str]) -> Result<Self, &'static str> {
+        Grid::from_commands_with_size(Grid::GRID_SIZE, Grid::GRID_SIZE, commands)
+    }
+}
+
+impl<M: LightModel> Grid<M> {
+    pub fn with_size(width: usize, height: usize) -> Self {
         Grid {
-            values: vec![0; Grid::GRID_SIZE * Grid::GRID_SIZE],
+            width,
+            height,
+            xs: vec![0],
+            ys: vec![0],
+            blocks: vec![vec![M::State::default()]],
+        }
+    }
+
+    pub fn from_commands_with_size(
+        width: usize,
+        height: usize,
+        commands: &[&str],
+    ) -> Result<Self, &'static str> {
+        let mut grid = Grid::with_size(width, height);
+        for command in commands {
+            grid.apply(parse_action_within(command, width, height)?);
+        }
+        Ok(grid)
+    }
+
+    // index of the block column starting at x, splitting the one containing
+    // it if needed
+    fn split_column(&mut self, x: usize) -> usize {
+        match self.xs.binary_search(&x) {
+            Ok(i) => i,
+            Err(i) => {
+                self.xs.insert(i, x);
+                self.blocks.insert(i, self.blocks[i - 1].clone());
+                i
+            }
+        }
+    }
+
+    fn split_row(&mut self, y: usize) -> usize {
+        match self.ys.binary_search(&y) {
+            Ok(i) => i,
+            Err(i) => {
+                self.ys.insert(i, y);
+                for column in &mut self.blocks {
+                    column.insert(i, column[i - 1]);
+                }
+                i
+            }
+        }
+    }
+
+    // merges neighbouring block columns and rows holding the same states
+    fn compact(&mut self) {
+        for i in (1..self.xs.len()).rev() {
+            if self.blocks[i] == self.blocks[i - 1] {
+                self.xs.remove(i);
+                self.blocks.remove(i);
+            }
+        }
+        for j in (1..self.ys.len()).rev() {
+            if self.blocks.iter().all(|column| column[j] == column[j - 1]) {
+                self.ys.remove(j);
+                for column in &mut self.blocks {
+                    column.remove(j);
+                }
+            }
         }
     }
 
     fn apply(&mut self, act: GridAction) -> &mut Self {
-        use GridAction::*;
-        let rect = match &act {
-            On(rect) => rect,
-            Off(rect) => rect,
-            Toggle(rect) => rect,
+        let (action, rect) = match &act {
+            GridAction::On(rect) => (Action::On, rect),
+            GridAction::Off(rect) => (Action::Off, rect),
+            GridAction::Toggle(rect) => (Action::Toggle, rect),
+        };
+        let x_start = self.split_column(rect.corner_1.x);
+        let x_end = if rect.corner_2.x + 1 < self.width {
+            self.split_column(rect.corner_2.x + 1)
+        } else {
+            self.xs.len()
         };
-        for x in rect.corner_1.x..rect.corner_2.x + 1 {
-            for y in rect.corner_1.y..rect.corner_2.y + 1 {
-                let index = Grid::GRID_SIZE * x + y;
-                match act {
-                    On(_) => self.values[index] += 1,
-                    Off(_) => {
-                        if self.values[index] > 0 {
-                            self.values[index] -= 1;
-                        }
-                    }
-                    Toggle(_) => self.values[index] += 2,
-                };
+        let y_start = self.split_row(rect.corner_1.y);
+        let y_end = if rect.corner_2.y + 1 < self.height {
+            self.split_row(rect.corner_2.y + 1)
+        } else {
+            self.ys.len()
+        };
+        for column in &mut self.blocks[x_start..x_end] {
+            for state in &mut column[y_start..y_end] {
+                *state = M::apply(action, *state);
             }
         }
+        self.compact();
         self
     }
 
-    pub fn from_commands(commands: &[&str]) -> Result<Self, &'static str> {
-        let mut grid = Grid::new();
-        for command in commands {
-            grid.apply(parse_action(command)?);
-        }
-        Ok(grid)
+    fn spans(starts: &[usize], end: usize) -> impl Iterator<Item = u64> + '_ {
+        starts
+            .iter()
+            .copied()
+            .zip(starts.iter().skip(1).copied().chain([end]))
+            .map(|(start, end)| (end - start) as u64)
     }
 
     pub fn total_brightness(&self) -> u64 {
-        self.values.iter().sum()
+        Self::spans(&self.xs, self.width)
+            .zip(&self.blocks)
+            .map(|(width, column)| {
+                Self::spans(&self.ys, self.height)
+                    .zip(column)
+                    .map(|(height, &state)| width * height * M::brightness(state))
+                    .sum::<u64>()
+            })
+            .sum()
+    }
+
+    pub fn brightness_at(&self, x: usize, y: usize) -> u64 {
+        let i = self.xs.partition_point(|&start| start <= x) - 1;
+        let j = self.ys.partition_point(|&start| start <= y) - 1;
+        M::brightness(self.blocks[i][j])
+    }
+
+    // grayscale image of the grid sampled at `image_width` x `image_height`
+    // points, the brightest light being white
+    pub fn render(&self, image_width: usize, image_height: usize) -> Vec<u8> {
+        let max_brightness = self
+            .blocks
+            .iter()
+            .flatten()
+            .map(|&state| M::brightness(state))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut image = Vec::with_capacity(image_width * image_height);
+        for py in 0..image_height {
+            for px in 0..image_width {
+                let x = px * self.width / image_width;
+                let y = py * self.height / image_height;
+                image.push((self.brightness_at(x, y) * 255 / max_brightness) as u8);
+            }
+        }
+        image
+    }
+
+    pub fn write_png<W: Write>(
+        &self,
+        out: W,
+        image_width: usize,
+        image_height: usize,
+    ) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(out, image_width as u32, image_height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.render(image_width, image_height))
+    }
+}
+
+impl Default for Grid<Brightness> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Point {
-    pub fn parse(s: &str) -> Result<Point, &'static str> {
+    pub fn parse_within(s: &str, width: usize, height: usize) -> Result<Point, &'static str> {
         let parsed = sscanf!(s, "{usize},{usize}");
         if parsed.is_err() {
             return Err("can't parse coords");
         }
         let (x, y) = parsed.unwrap();
-        if x >= Grid::GRID_SIZE || y >= Grid::GRID_SIZE {
+        if x >= width || y >= height {
             return Err("point out of bounds");
         }
         Ok(Point { x, y })
@@ -99,11 +289,11 @@ impl Rect {
     }
 }
 
-fn parse_action(s: &str) -> Result<GridAction, &'static str> {
+fn parse_action_within(s: &str, width: usize, height: usize) -> Result<GridAction, &'static str> {
     let tokens: Vec<_> = s.split(' ').collect();
     if tokens.len() == 5 && tokens[0] == "turn" {
-        let corner_1 = Point::parse(tokens[2])?;
-        let corner_2 = Point::parse(tokens[4])?;
+        let corner_1 = Point::parse_within(tokens[2], width, height)?;
+        let corner_2 = Point::parse_within(tokens[4], width, height)?;
         let rect = Rect::new(&corner_1, &corner_2);
         match tokens[1] {
             "on" => return Ok(GridAction::On(rect)),
@@ -111,8 +301,8 @@ fn parse_action(s: &str) -> Result<GridAction, &'static str> {
             _ => return Err("invalid token after turn"),
         }
     } else if tokens.len() == 4 && tokens[0] == "toggle" {
-        let corner_1 = Point::parse(tokens[1])?;
-        let corner_2 = Point::parse(tokens[3])?;
+        let corner_1 = Point::parse_within(tokens[1], width, height)?;
+        let corner_2 = Point::parse_within(tokens[3], width, height)?;
         let rect = Rect::new(&corner_1, &corner_2);
         return Ok(GridAction::Toggle(rect));
     }
@@ -125,17 +315,26 @@ mod tests {
 
     #[test]
     fn point_parse_test_1() {
-        assert_eq!(Ok(Point { x: 567, y: 19 }), Point::parse("567,19"));
+        assert_eq!(
+            Ok(Point { x: 567, y: 19 }),
+            Point::parse_within("567,19", 1000, 1000)
+        );
     }
 
     #[test]
     fn point_parse_test_2() {
-        assert_eq!(Err("can't parse coords"), Point::parse("hello"));
+        assert_eq!(
+            Err("can't parse coords"),
+            Point::parse_within("hello", 1000, 1000)
+        );
     }
 
     #[test]
     fn point_parse_test_3() {
-        assert_eq!(Err("point out of bounds"), Point::parse("1354,90"));
+        assert_eq!(
+            Err("point out of bounds"),
+            Point::parse_within("1354,90", 1000, 1000)
+        );
     }
 
     #[test]
@@ -145,7 +344,7 @@ mod tests {
                 corner_1: Point { x: 567, y: 23 },
                 corner_2: Point { x: 975, y: 800 }
             })),
-            parse_action("toggle 975,23 through 567,800")
+            parse_action_within("toggle 975,23 through 567,800", 1000, 1000)
         )
     }
 
@@ -156,7 +355,7 @@ mod tests {
                 corner_1: Point { x: 111, y: 504 },
                 corner_2: Point { x: 111, y: 877 }
             })),
-            parse_action("turn on 111,877 through 111,504")
+            parse_action_within("turn on 111,877 through 111,504", 1000, 1000)
         )
     }
 
@@ -176,4 +375,83 @@ mod tests {
             .apply(GridAction::Off(Rect::new(&a, &b)));
         assert_eq!(fresh, modified);
     }
+
+    #[test]
+    fn total_brightness_test_1() {
+        let grid = Grid::from_commands(&["turn on 0,0 through 0,0", "toggle 0,0 through 999,999"])
+            .unwrap();
+        assert_eq!(grid.total_brightness(), 2000001);
+        let commands = [
+            "turn on 0,0 through 999,999",
+            "toggle 0,0 through 999,0",
+            "turn off 499,499 through 500,500",
+        ];
+        let grid = Grid::<Binary>::from_commands_with_size(1000, 1000, &commands).unwrap();
+        assert_eq!(grid.total_brightness(), 1000000 - 1000 - 4);
+    }
+
+    #[test]
+    fn total_brightness_test_2() {
+        // compare with a plain array on a small grid
+        let commands = [
+            "turn on 2,3 through 7,9",
+            "toggle 0,0 through 4,4",
+            "turn off 3,0 through 9,5",
+            "toggle 9,9 through 1,6",
+            "turn off 0,0 through 1,9",
+        ];
+        let mut naive = [[0u64; 10]; 10];
+        for command in commands {
+            let (action, rect) = match parse_action_within(command, 10, 10).unwrap() {
+                GridAction::On(rect) => (Action::On, rect),
+                GridAction::Off(rect) => (Action::Off, rect),
+                GridAction::Toggle(rect) => (Action::Toggle, rect),
+            };
+            for column in &mut naive[rect.corner_1.x..=rect.corner_2.x] {
+                for state in &mut column[rect.corner_1.y..=rect.corner_2.y] {
+                    *state = Brightness::apply(action, *state);
+                }
+            }
+        }
+        let grid = Grid::<Brightness>::from_commands_with_size(10, 10, &commands).unwrap();
+        assert_eq!(grid.total_brightness(), naive.iter().flatten().sum::<u64>());
+        for (x, column) in naive.iter().enumerate() {
+            for (y, &state) in column.iter().enumerate() {
+                assert_eq!(grid.brightness_at(x, y), state);
+            }
+        }
+    }
+
+    #[test]
+    fn total_brightness_test_3() {
+        let commands = [
+            "turn on 0,0 through 999999,999999",
+            "toggle 1,1 through 999998,999998",
+            "turn off 123456,0 through 123456,999999",
+        ];
+        let grid = Grid::<Binary>::from_commands_with_size(1000000, 1000000, &commands).unwrap();
+        assert_eq!(grid.total_brightness(), 4 * 999999 - 2);
+        assert_eq!(
+            Grid::<Binary>::from_commands_with_size(10, 10, &commands),
+            Err("point out of bounds")
+        );
+    }
+
+    #[test]
+    fn render_test_1() {
+        let mut grid = Grid::<Brightness>::with_size(4, 2);
+        grid.apply(GridAction::On(Rect::new(
+            &Point { x: 0, y: 0 },
+            &Point { x: 1, y: 1 },
+        )))
+        .apply(GridAction::Toggle(Rect::new(
+            &Point { x: 0, y: 0 },
+            &Point { x: 0, y: 0 },
+        )));
+        assert_eq!(grid.render(4, 2), [255, 85, 0, 0, 85, 85, 0, 0]);
+        assert_eq!(grid.render(2, 1), [255, 0]);
+        let mut out = vec![];
+        grid.write_png(&mut out, 8, 4).unwrap();
+        assert!(out.starts_with(b"\x89PNG"));
+    }
 }
@@ -1,9 +1,19 @@
-use day6_2::Grid;
-use std::fs::read_to_string;
+use day6_2::{Binary, Grid};
+use std::{env, fs::read_to_string, fs::File};
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
     let input: Vec<_> = input.trim().split('\n').collect();
     let grid = Grid::from_commands(&input).unwrap();
+    let lights = Grid::<Binary>::from_commands_with_size(1000, 1000, &input).unwrap();
+    println!("lights on: {}", lights.total_brightness());
     println!("total brightness: {}", grid.total_brightness());
+
+    let args = env::args().collect::<Vec<_>>();
+    if let [_, flag, path] = args.as_slice() {
+        if flag == "--png" {
+            grid.write_png(File::create(path).unwrap(), 1000, 1000)
+                .unwrap();
+        }
+    }
 }
@@ -1,5 +1,37 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
 
+use itertools::Itertools;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EvalError {
+    #[error("wire does not exist: {0}")]
+    MissingWire(String),
+    #[error("wires form a cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum WireSyntaxError {
+    #[error("invalid command")]
+    InvalidCommand,
+    #[error("invalid operator: {0}")]
+    InvalidOperator(String),
+    #[error("cannot parse shift length")]
+    InvalidShift,
+    #[error("invalid wire name: {0}")]
+    InvalidName(String),
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("line #{line_number}: {source}")]
+pub struct ParseError {
+    line_number: usize,
+    source: WireSyntaxError,
+}
+
+#[derive(Debug, PartialEq)]
 enum Wire {
     Copy(String),
     Not(String),
@@ -9,111 +41,460 @@ enum Wire {
     RShift(String, u16),
 }
 
+impl Wire {
+    fn operands(&self) -> Vec<&str> {
+        use Wire::*;
+        match self {
+            Copy(w) | Not(w) | LShift(w, _) | RShift(w, _) => vec![w],
+            And(w1, w2) | Or(w1, w2) => vec![w1, w2],
+        }
+    }
+
+    fn compute(&self, signal: impl Fn(&str) -> u16) -> u16 {
+        use Wire::*;
+        match self {
+            Copy(w) => signal(w),
+            Not(w) => !signal(w),
+            And(w1, w2) => signal(w1) & signal(w2),
+            Or(w1, w2) => signal(w1) | signal(w2),
+            LShift(w, sh) => signal(w) << sh,
+            RShift(w, sh) => signal(w) >> sh,
+        }
+    }
+}
+
+impl fmt::Display for Wire {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Wire::*;
+        match self {
+            Copy(w) => write!(f, "{w}"),
+            Not(w) => write!(f, "NOT {w}"),
+            And(w1, w2) => write!(f, "{w1} AND {w2}"),
+            Or(w1, w2) => write!(f, "{w1} OR {w2}"),
+            LShift(w, sh) => write!(f, "{w} LSHIFT {sh}"),
+            RShift(w, sh) => write!(f, "{w} RSHIFT {sh}"),
+        }
+    }
+}
+
+fn parse_name(s: &str) -> Result<String, WireSyntaxError> {
+    let mut chars = s.chars();
+    let starts_with_letter = chars.next().is_some_and(|c| c.is_ascii_lowercase());
+    if starts_with_letter && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+        Ok(s.to_owned())
+    } else {
+        Err(WireSyntaxError::InvalidName(s.to_owned()))
+    }
+}
+
+fn parse_operand(s: &str) -> Result<String, WireSyntaxError> {
+    match s.parse::<u16>() {
+        Ok(_) => Ok(s.to_owned()),
+        Err(_) => parse_name(s),
+    }
+}
+
+fn parse_shift(s: &str) -> Result<u16, WireSyntaxError> {
+    s.parse().map_err(|_| WireSyntaxError::InvalidShift)
+}
+
+fn parse_wire(s: &str) -> Result<(String, Wire), WireSyntaxError> {
+    use Wire::*;
+    let tokens: Vec<_> = s.split(' ').collect();
+    let wire = match tokens[..] {
+        [w, "->", _] => Copy(parse_operand(w)?),
+        ["NOT", w, "->", _] => Not(parse_operand(w)?),
+        [w1, op, w2, "->", _] => match op {
+            "AND" => And(parse_operand(w1)?, parse_operand(w2)?),
+            "OR" => Or(parse_operand(w1)?, parse_operand(w2)?),
+            "LSHIFT" => LShift(parse_operand(w1)?, parse_shift(w2)?),
+            "RSHIFT" => RShift(parse_operand(w1)?, parse_shift(w2)?),
+            _ => return Err(WireSyntaxError::InvalidOperator(op.to_owned())),
+        },
+        _ => return Err(WireSyntaxError::InvalidCommand),
+    };
+    Ok((parse_name(tokens[tokens.len() - 1])?, wire))
+}
+
 #[derive(Default)]
 pub struct Circuit {
     state: HashMap<String, Wire>,
+    overrides: HashMap<String, u16>,
+    // wires reading each wire, kept in sync with `state`
+    dependents: HashMap<String, Vec<String>>,
+    // signals from the last full or incremental evaluation
+    values: HashMap<String, u16>,
+    changed: Vec<String>,
 }
 
 impl Circuit {
-    pub fn new(s: &[&str]) -> Result<Circuit, &'static str> {
+    pub fn new(s: &[&str]) -> Result<Circuit, ParseError> {
         let mut cir = Self::default();
-        for wire in s {
-            cir.add_wire(wire)?;
+        for (line_number, wire) in s.iter().enumerate() {
+            cir.add_wire(wire).map_err(|source| ParseError {
+                line_number,
+                source,
+            })?;
         }
         Ok(cir)
     }
 
-    pub fn add_wire(&mut self, s: &str) -> Result<(), &'static str> {
-        use Wire::*;
-        let tokens: Vec<_> = s.split(' ').collect();
-        if tokens.len() == 3 {
-            let name = tokens[2].to_owned();
-            self.state.insert(name, Copy(tokens[0].to_owned()));
-            return Ok(());
-        }
-        if tokens.len() == 4 && tokens[0] == "NOT" {
-            let name = tokens[3].to_owned();
-            self.state.insert(name, Not(tokens[1].to_owned()));
-            return Ok(());
-        }
-        if tokens.len() == 5 {
-            let name = tokens[4].to_owned();
-            match tokens[1] {
-                "AND" => {
-                    self.state
-                        .insert(name, And(tokens[0].to_owned(), tokens[2].to_owned()));
+    fn names(&self) -> Vec<&str> {
+        self.state.keys().map(String::as_str).sorted().collect()
+    }
+
+    // wires that become module inputs: those driven by a literal or an
+    // override, and those used without being defined
+    fn inputs_for_export(&self) -> Vec<&str> {
+        let defined_inputs = self.state.iter().filter_map(|(name, wire)| match wire {
+            Wire::Copy(w) if w.parse::<u16>().is_ok() => Some(name.as_str()),
+            _ if self.overrides.contains_key(name) => Some(name.as_str()),
+            _ => None,
+        });
+        let undefined = self
+            .dependents
+            .keys()
+            .filter(|name| !self.state.contains_key(*name))
+            .map(String::as_str);
+        defined_inputs.chain(undefined).sorted().dedup().collect()
+    }
+
+    pub fn to_verilog(&self, module: &str) -> String {
+        let operand = |w: &str| match w.parse::<u16>() {
+            Ok(c) => format!("16'd{c}"),
+            Err(_) => format!("w_{w}"),
+        };
+        let inputs = self.inputs_for_export();
+        let is_output = |name: &str| {
+            self.dependents
+                .get(name)
+                .is_none_or(|dependents| dependents.is_empty())
+        };
+        let outputs = self
+            .names()
+            .into_iter()
+            .filter(|name| !inputs.contains(name) && is_output(name))
+            .collect::<Vec<_>>();
+        let ports = inputs
+            .iter()
+            .map(|name| format!("    input wire [15:0] w_{name}"))
+            .chain(
+                outputs
+                    .iter()
+                    .map(|name| format!("    output wire [15:0] w_{name}")),
+            )
+            .join(",\n");
+
+        let mut verilog = format!("module {module} (\n{ports}\n);\n");
+        let internal = self
+            .names()
+            .into_iter()
+            .filter(|name| !inputs.contains(name) && !outputs.contains(name))
+            .collect::<Vec<_>>();
+        for name in &internal {
+            writeln!(verilog, "    wire [15:0] w_{name};").unwrap();
+        }
+        for name in self.names() {
+            if inputs.contains(&name) {
+                continue;
+            }
+            let expression = match &self.state[name] {
+                Wire::Copy(w) => operand(w),
+                Wire::Not(w) => format!("~{}", operand(w)),
+                Wire::And(w1, w2) => format!("{} & {}", operand(w1), operand(w2)),
+                Wire::Or(w1, w2) => format!("{} | {}", operand(w1), operand(w2)),
+                Wire::LShift(w, sh) => format!("{} << {sh}", operand(w)),
+                Wire::RShift(w, sh) => format!("{} >> {sh}", operand(w)),
+            };
+            writeln!(verilog, "    assign w_{name} = {expression};").unwrap();
+        }
+        verilog.push_str("endmodule\n");
+        verilog
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph circuit {\n");
+        for name in self.inputs_for_export() {
+            writeln!(dot, "    \"{name}\" [shape=box];").unwrap();
+        }
+        for name in self.names() {
+            let label = match &self.state[name] {
+                Wire::Copy(_) => None,
+                Wire::Not(_) => Some("NOT".to_owned()),
+                Wire::And(..) => Some("AND".to_owned()),
+                Wire::Or(..) => Some("OR".to_owned()),
+                Wire::LShift(_, sh) => Some(format!("LSHIFT {sh}")),
+                Wire::RShift(_, sh) => Some(format!("RSHIFT {sh}")),
+            };
+            if let Some(label) = label {
+                writeln!(dot, "    \"{name}\" [label=\"{name}\\n{label}\"];").unwrap();
+            }
+        }
+        for name in self.names() {
+            for operand in self.state[name].operands() {
+                if operand.parse::<u16>().is_err() {
+                    writeln!(dot, "    \"{operand}\" -> \"{name}\";").unwrap();
                 }
-                "OR" => {
-                    self.state
-                        .insert(name, Or(tokens[0].to_owned(), tokens[2].to_owned()));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn insert_wire(&mut self, name: String, wire: Wire) {
+        if let Some(old) = self.state.get(&name) {
+            for operand in old.operands() {
+                if let Some(dependents) = self.dependents.get_mut(operand) {
+                    dependents.retain(|dependent| *dependent != name);
                 }
-                "RSHIFT" => {
-                    let shift: u16 = match tokens[2].parse() {
-                        Ok(i) => Ok(i),
-                        Err(_) => Err("cannot parse shift length"),
-                    }?;
-                    self.state.insert(name, RShift(tokens[0].to_owned(), shift));
+            }
+        }
+        for operand in wire.operands() {
+            if operand.parse::<u16>().is_err() {
+                let dependents = self.dependents.entry(operand.to_owned()).or_default();
+                dependents.push(name.clone());
+            }
+        }
+        self.state.insert(name, wire);
+        self.values.clear();
+    }
+
+    pub fn add_wire(&mut self, s: &str) -> Result<(), WireSyntaxError> {
+        let (name, wire) = parse_wire(s)?;
+        self.insert_wire(name, wire);
+        Ok(())
+    }
+
+    // fixes the signal of a wire regardless of how it is defined, until the
+    // override is cleared
+    pub fn override_wire(&mut self, name: &str, value: u16) {
+        self.overrides.insert(name.to_owned(), value);
+        self.values.clear();
+    }
+
+    pub fn clear_override(&mut self, name: &str) -> Option<u16> {
+        self.values.clear();
+        self.overrides.remove(name)
+    }
+
+    // overrides `name` like `override_wire`, then recomputes only the wires
+    // downstream of it whose inputs actually changed
+    pub fn set_wire(&mut self, name: &str, value: u16) -> Result<(), EvalError> {
+        self.changed.clear();
+        if self.values.is_empty() {
+            match self.eval_all() {
+                Ok(values) => self.values = values,
+                // the circuit only settles with the override, so there is
+                // nothing to compare against and every wire counts as changed
+                Err(_) => {
+                    self.overrides.insert(name.to_owned(), value);
+                    self.values = self.eval_all()?;
+                    self.changed = self.values.keys().cloned().sorted().collect();
+                    return Ok(());
                 }
-                "LSHIFT" => {
-                    let shift: u16 = match tokens[2].parse() {
-                        Ok(i) => Ok(i),
-                        Err(_) => Err("cannot parse shift length"),
-                    }?;
-                    self.state.insert(name, LShift(tokens[0].to_owned(), shift));
+            }
+        }
+        self.overrides.insert(name.to_owned(), value);
+
+        let mut changed = HashSet::new();
+        if self.values.insert(name.to_owned(), value) != Some(value) {
+            changed.insert(name.to_owned());
+        }
+        let mut downstream = HashSet::new();
+        let mut stack = vec![name];
+        while let Some(wire) = stack.pop() {
+            for dependent in self.dependents.get(wire).into_iter().flatten() {
+                if downstream.insert(dependent.as_str()) {
+                    stack.push(dependent);
                 }
-                _ => return Err("invalid operator"),
+            }
+        }
+        // inputs are counted as in `eval_wires`, so an overridden wire does
+        // not wait on the wires it no longer reads
+        let mut inputs = HashMap::new();
+        for &wire in &downstream {
+            let wire_inputs = if self.overrides.contains_key(wire) {
+                vec![]
+            } else {
+                self.state[wire].operands()
             };
-            return Ok(());
+            inputs.insert(
+                wire,
+                wire_inputs
+                    .into_iter()
+                    .filter(|input| downstream.contains(input))
+                    .collect::<Vec<_>>(),
+            );
+        }
+        let mut pending = inputs
+            .iter()
+            .map(|(&wire, wire_inputs)| (wire, wire_inputs.len()))
+            .collect::<HashMap<_, _>>();
+        let mut outputs = HashMap::<&str, Vec<&str>>::new();
+        for (&wire, wire_inputs) in &inputs {
+            for &input in wire_inputs {
+                outputs.entry(input).or_default().push(wire);
+            }
+        }
+        let mut ready = pending
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(&wire, _)| wire)
+            .collect::<Vec<_>>();
+        let mut done = HashSet::new();
+        while let Some(wire) = ready.pop() {
+            done.insert(wire);
+            let definition = &self.state[wire];
+            if !self.overrides.contains_key(wire)
+                && definition
+                    .operands()
+                    .into_iter()
+                    .any(|operand| changed.contains(operand))
+            {
+                let values = &self.values;
+                let new_value = definition
+                    .compute(|operand| operand.parse::<u16>().unwrap_or_else(|_| values[operand]));
+                if self.values.insert(wire.to_owned(), new_value) != Some(new_value) {
+                    changed.insert(wire.to_owned());
+                }
+            }
+            for &output in outputs.get(wire).into_iter().flatten() {
+                let count = pending.get_mut(output).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(output);
+                }
+            }
         }
-        Err("invalid command")
+
+        if done.len() < inputs.len() {
+            // the stale signals left in the cycle can't be trusted anymore
+            let cycle = find_cycle(&inputs, |wire| done.contains(wire));
+            self.values.clear();
+            return Err(EvalError::Cycle(cycle));
+        }
+        self.changed = changed.into_iter().sorted().collect();
+        Ok(())
     }
 
-    pub fn eval(&self, name: &str) -> Result<u16, String> {
-        let mut table: HashMap<String, u16> = HashMap::new();
-        self.eval_with_table(name, &mut table)
+    // wires whose signal was changed by the last `set_wire`
+    pub fn changed_wires(&self) -> &[String] {
+        &self.changed
     }
 
-    fn eval_with_table(&self, name: &str, table: &mut HashMap<String, u16>) -> Result<u16, String> {
-        use Wire::*;
+    // signal of a wire as of the last `set_wire`
+    pub fn signal(&self, name: &str) -> Option<u16> {
+        self.values.get(name).copied()
+    }
+
+    pub fn eval(&self, name: &str) -> Result<u16, EvalError> {
         if let Ok(c) = name.parse::<u16>() {
             return Ok(c);
         }
-        if let Some(&c) = table.get(name) {
-            return Ok(c);
+        let values = self.eval_wires([name])?;
+        Ok(values[name])
+    }
+
+    pub fn eval_all(&self) -> Result<HashMap<String, u16>, EvalError> {
+        self.eval_wires(self.state.keys().map(String::as_str))
+    }
+
+    // names of the wires feeding `name`; overridden wires have no inputs
+    fn inputs(&self, name: &str) -> Result<Vec<&str>, EvalError> {
+        if self.overrides.contains_key(name) {
+            return Ok(vec![]);
         }
-        let wire = match self.state.get(name) {
-            Some(w) => Ok(w),
-            None => Err(format!("wire does not exist: {name}")),
-        }?;
-        let result = match wire {
-            Copy(w) => self.eval_with_table(w, table),
-            Not(w) => match self.eval_with_table(w, table) {
-                Ok(c) => Ok(!c),
-                Err(e) => Err(e),
-            },
-            And(w1, w2) => {
-                let c1 = self.eval_with_table(w1, table)?;
-                let c2 = self.eval_with_table(w2, table)?;
-                Ok(c1 & c2)
-            }
-            Or(w1, w2) => {
-                let c1 = self.eval_with_table(w1, table)?;
-                let c2 = self.eval_with_table(w2, table)?;
-                Ok(c1 | c2)
+        let wire = self
+            .state
+            .get(name)
+            .ok_or_else(|| EvalError::MissingWire(name.to_owned()))?;
+        Ok(wire
+            .operands()
+            .into_iter()
+            .filter(|operand| operand.parse::<u16>().is_err())
+            .collect())
+    }
+
+    // evaluates the given wires and everything they depend on, in
+    // topological order
+    fn eval_wires<'a>(
+        &'a self,
+        roots: impl IntoIterator<Item = &'a str>,
+    ) -> Result<HashMap<String, u16>, EvalError> {
+        let mut inputs = HashMap::new();
+        let mut stack = roots.into_iter().collect::<Vec<_>>();
+        while let Some(name) = stack.pop() {
+            if inputs.contains_key(name) {
+                continue;
             }
-            RShift(w, sh) => {
-                let c = self.eval_with_table(w, table)?;
-                Ok(c >> sh)
+            let wire_inputs = self.inputs(name)?;
+            stack.extend(&wire_inputs);
+            inputs.insert(name, wire_inputs);
+        }
+
+        let mut pending = inputs
+            .iter()
+            .map(|(&name, wire_inputs)| (name, wire_inputs.len()))
+            .collect::<HashMap<_, _>>();
+        let mut outputs = HashMap::<&str, Vec<&str>>::new();
+        for (&name, wire_inputs) in &inputs {
+            for &input in wire_inputs {
+                outputs.entry(input).or_default().push(name);
             }
-            LShift(w, sh) => {
-                let c = self.eval_with_table(w, table)?;
-                Ok(c << sh)
+        }
+        let mut ready = pending
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(&name, _)| name)
+            .collect::<Vec<_>>();
+        let mut values = HashMap::new();
+        while let Some(name) = ready.pop() {
+            let value = match self.overrides.get(name) {
+                Some(&value) => value,
+                None => self.state[name]
+                    .compute(|operand| operand.parse::<u16>().unwrap_or_else(|_| values[operand])),
+            };
+            values.insert(name.to_owned(), value);
+            for &output in outputs.get(name).into_iter().flatten() {
+                let count = pending.get_mut(output).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(output);
+                }
             }
-        };
-        if let Ok(r) = result {
-            table.insert(name.to_owned(), r);
         }
-        result
+
+        if values.len() < inputs.len() {
+            return Err(EvalError::Cycle(find_cycle(&inputs, |name| {
+                values.contains_key(name)
+            })));
+        }
+        Ok(values)
+    }
+}
+
+// one instruction per line in the puzzle syntax, sorted by wire name;
+// overrides are not part of the listing
+impl fmt::Display for Circuit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in self.names() {
+            writeln!(f, "{} -> {name}", self.state[name])?;
+        }
+        Ok(())
+    }
+}
+
+// every wire left unevaluated after the topological sort waits on another
+// such wire, so following those inputs from any of them ends in a cycle
+fn find_cycle(inputs: &HashMap<&str, Vec<&str>>, is_done: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut path = vec![*inputs.keys().filter(|name| !is_done(name)).min().unwrap()];
+    loop {
+        let last = path[path.len() - 1];
+        let next = *inputs[last].iter().find(|input| !is_done(input)).unwrap();
+        if let Some(start) = path.iter().position(|&name| name == next) {
+            return path[start..].iter().map(|&name| name.to_owned()).collect();
+        }
+        path.push(next);
     }
 }
 
@@ -145,4 +526,248 @@ mod tests {
         let cir = Circuit::new(&wires).unwrap();
         assert_eq!(Ok(255), cir.eval("c"));
     }
+
+    #[test]
+    fn circuit_eval_all_test_1() {
+        // example from the puzzle description
+        let wires = [
+            "123 -> x",
+            "456 -> y",
+            "x AND y -> d",
+            "x OR y -> e",
+            "x LSHIFT 2 -> f",
+            "y RSHIFT 2 -> g",
+            "NOT x -> h",
+            "NOT y -> i",
+        ];
+        let values = Circuit::new(&wires).unwrap().eval_all().unwrap();
+        let expected = [
+            ("d", 72),
+            ("e", 507),
+            ("f", 492),
+            ("g", 114),
+            ("h", 65412),
+            ("i", 65079),
+            ("x", 123),
+            ("y", 456),
+        ];
+        assert_eq!(
+            values,
+            expected
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value))
+                .collect()
+        );
+    }
+
+    #[test]
+    fn circuit_eval_test_1() {
+        let wires = ["b -> a", "c AND 1 -> b", "a -> c", "5 -> d", "d -> e"];
+        let cir = Circuit::new(&wires).unwrap();
+        let cycle = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        assert_eq!(cir.eval("a"), Err(EvalError::Cycle(cycle.clone())));
+        assert_eq!(cir.eval_all(), Err(EvalError::Cycle(cycle)));
+        assert_eq!(cir.eval("e"), Ok(5));
+    }
+
+    #[test]
+    fn circuit_eval_test_2() {
+        let cir = Circuit::new(&["x OR y -> z", "1 -> x"]).unwrap();
+        assert_eq!(cir.eval("z"), Err(EvalError::MissingWire("y".to_owned())));
+        assert_eq!(cir.eval("x"), Ok(1));
+    }
+
+    #[test]
+    fn circuit_eval_test_3() {
+        // a long chain must not overflow the stack
+        let wires = (0..20_000)
+            .map(|i| format!("w{i} -> w{}", i + 1))
+            .chain(["7 -> w0".to_owned()])
+            .collect::<Vec<_>>();
+        let wires = wires.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(Circuit::new(&wires).unwrap().eval("w20000"), Ok(7));
+    }
+
+    #[test]
+    fn circuit_override_wire_test_1() {
+        let wires = ["b -> a", "a -> b", "3 -> c", "b AND c -> d"];
+        let mut cir = Circuit::new(&wires).unwrap();
+        cir.override_wire("b", 6);
+        assert_eq!(cir.eval("d"), Ok(2));
+        assert_eq!(cir.eval("a"), Ok(6));
+        assert_eq!(cir.clear_override("b"), Some(6));
+        assert!(cir.eval("d").is_err());
+    }
+
+    #[test]
+    fn circuit_set_wire_test_1() {
+        let wires = [
+            "3 -> x",
+            "5 -> y",
+            "x AND y -> d",
+            "x OR y -> e",
+            "d LSHIFT 1 -> f",
+            "y RSHIFT 1 -> g",
+        ];
+        let mut cir = Circuit::new(&wires).unwrap();
+        cir.set_wire("x", 3).unwrap();
+        assert!(cir.changed_wires().is_empty());
+        assert_eq!(cir.signal("f"), Some(2));
+
+        // 7 & 5 == 3 & 5 | 4, so d and f change while g does not depend on x
+        cir.set_wire("x", 7).unwrap();
+        assert_eq!(cir.changed_wires(), ["d", "f", "x"]);
+        assert_eq!(cir.signal("d"), Some(5));
+        assert_eq!(cir.signal("f"), Some(10));
+
+        // 6 | 5 == 7 | 5 but 6 & 5 differs
+        cir.set_wire("x", 6).unwrap();
+        assert_eq!(cir.changed_wires(), ["d", "f", "x"]);
+        assert_eq!(cir.signal("e"), Some(7));
+
+        cir.set_wire("x", 6).unwrap();
+        assert!(cir.changed_wires().is_empty());
+    }
+
+    #[test]
+    fn circuit_set_wire_test_2() {
+        let wires = ["1 -> a", "a -> b", "b AND a -> c", "NOT c -> d"];
+        let mut cir = Circuit::new(&wires).unwrap();
+        cir.set_wire("a", 1).unwrap();
+        assert!(cir.changed_wires().is_empty());
+        let mut fresh = Circuit::new(&wires).unwrap();
+        fresh.set_wire("a", 2).unwrap();
+        assert_eq!(fresh.changed_wires(), ["a", "b", "c", "d"]);
+        for value in [0, 65535, 12345] {
+            cir.set_wire("a", value).unwrap();
+            let mut expected = Circuit::new(&wires).unwrap();
+            expected.override_wire("a", value);
+            for (name, signal) in expected.eval_all().unwrap() {
+                assert_eq!(cir.signal(&name), Some(signal));
+            }
+        }
+        cir.add_wire("c -> b").unwrap();
+        assert_eq!(
+            cir.set_wire("a", 2),
+            Err(EvalError::Cycle(vec!["b".to_owned(), "c".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn circuit_set_wire_test_3() {
+        // the override breaks the feedback loop between a and b
+        let wires = ["b -> a", "a -> b", "a AND 3 -> d"];
+        let mut cir = Circuit::new(&wires).unwrap();
+        cir.set_wire("b", 6).unwrap();
+        assert_eq!(cir.signal("d"), Some(2));
+        cir.set_wire("b", 7).unwrap();
+        assert_eq!(cir.changed_wires(), ["a", "b", "d"]);
+        assert_eq!(cir.signal("a"), Some(7));
+        assert_eq!(cir.signal("d"), Some(3));
+        cir.set_wire("a", 1).unwrap();
+        assert_eq!(cir.changed_wires(), ["a", "d"]);
+        assert_eq!(cir.signal("b"), Some(7));
+        assert_eq!(cir.signal("d"), Some(1));
+    }
+
+    #[test]
+    fn circuit_new_test_2() {
+        let wires = ["1 -> a", "a AND b -> c", "a XOR b -> d"];
+        assert_eq!(
+            Circuit::new(&wires).err(),
+            Some(ParseError {
+                line_number: 2,
+                source: WireSyntaxError::InvalidOperator("XOR".to_owned())
+            })
+        );
+        let wires = ["a LSHIFT x -> b"];
+        assert_eq!(
+            Circuit::new(&wires).err().map(|e| e.to_string()),
+            Some("line #0: cannot parse shift length".to_owned())
+        );
+        let wires = ["1 -> a", "a -> B"];
+        assert_eq!(
+            Circuit::new(&wires).err(),
+            Some(ParseError {
+                line_number: 1,
+                source: WireSyntaxError::InvalidName("B".to_owned())
+            })
+        );
+        assert_eq!(
+            parse_wire("NOT a -> b -> c").err(),
+            Some(WireSyntaxError::InvalidCommand)
+        );
+    }
+
+    #[test]
+    fn circuit_display_test_1() {
+        let wires = [
+            "y RSHIFT 2 -> g",
+            "123 -> x",
+            "NOT x -> h",
+            "x AND 1 -> d",
+            "x OR y -> e",
+            "d LSHIFT 2 -> f",
+            "h -> y",
+        ];
+        let cir = Circuit::new(&wires).unwrap();
+        let listing = cir.to_string();
+        assert_eq!(
+            listing.lines().collect::<Vec<_>>(),
+            wires
+                .iter()
+                .copied()
+                .sorted_by_key(|w| w.split(' ').next_back())
+                .collect::<Vec<_>>()
+        );
+        let lines = listing.lines().collect::<Vec<_>>();
+        let reparsed = Circuit::new(&lines).unwrap();
+        assert_eq!(reparsed.state, cir.state);
+        assert_eq!(reparsed.eval_all(), cir.eval_all());
+    }
+
+    #[test]
+    fn circuit_to_verilog_test_1() {
+        let wires = [
+            "123 -> x",
+            "x AND y -> d",
+            "NOT d -> e",
+            "d LSHIFT 2 -> f",
+            "5 -> g",
+            "g -> a",
+        ];
+        let mut cir = Circuit::new(&wires).unwrap();
+        cir.override_wire("a", 1);
+        let expected = "\
+module circuit (
+    input wire [15:0] w_a,
+    input wire [15:0] w_g,
+    input wire [15:0] w_x,
+    input wire [15:0] w_y,
+    output wire [15:0] w_e,
+    output wire [15:0] w_f
+);
+    wire [15:0] w_d;
+    assign w_d = w_x & w_y;
+    assign w_e = ~w_d;
+    assign w_f = w_d << 2;
+endmodule
+";
+        assert_eq!(cir.to_verilog("circuit"), expected);
+    }
+
+    #[test]
+    fn circuit_to_dot_test_1() {
+        let wires = ["1 -> x", "x AND 3 -> d", "d -> e"];
+        let cir = Circuit::new(&wires).unwrap();
+        let expected = "\
+digraph circuit {
+    \"x\" [shape=box];
+    \"d\" [label=\"d\\nAND\"];
+    \"x\" -> \"d\";
+    \"d\" -> \"e\";
+}
+";
+        assert_eq!(cir.to_dot(), expected);
+    }
 }
@@ -7,6 +7,6 @@ fn main() {
     let input: Vec<_> = input.trim().split('\n').collect();
     let mut cir = Circuit::new(&input).unwrap();
     let initial = cir.eval("a").unwrap();
-    cir.add_wire(&format!("{initial} -> b")).unwrap();
+    cir.override_wire("b", initial);
     println!("a = {}", cir.eval("a").unwrap());
 }
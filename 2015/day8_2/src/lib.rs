@@ -1,5 +1,179 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LexError {
+    #[error("string literal must be enclosed in double quotes")]
+    MissingQuotes,
+    #[error("unescaped quote at position {0}")]
+    UnescapedQuote(usize),
+    #[error("escape sequence at position {0} is cut short")]
+    UnterminatedEscape(usize),
+    #[error("invalid escape sequence at position {0}")]
+    InvalidEscape(usize),
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("line #{line_number}: {source}")]
+pub struct ReportError {
+    line_number: usize,
+    source: LexError,
+}
+
+// one byte of the string in memory, along with how it is written in code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token {
+    Literal(u8),
+    // `\\` or `\"`
+    Escaped(u8),
+    // `\xNN`
+    Hex(u8),
+}
+
+impl Token {
+    // the shortest way to write `byte` inside a string literal
+    pub fn from_byte(byte: u8) -> Token {
+        match byte {
+            b'\\' | b'"' => Token::Escaped(byte),
+            b' '..=b'~' => Token::Literal(byte),
+            _ => Token::Hex(byte),
+        }
+    }
+
+    pub fn byte(self) -> u8 {
+        match self {
+            Token::Literal(byte) | Token::Escaped(byte) | Token::Hex(byte) => byte,
+        }
+    }
+
+    pub fn code_len(self) -> usize {
+        match self {
+            Token::Literal(_) => 1,
+            Token::Escaped(_) => 2,
+            Token::Hex(_) => 4,
+        }
+    }
+
+    fn write(self, out: &mut String) {
+        match self {
+            Token::Literal(byte) => out.push(byte as char),
+            Token::Escaped(byte) => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            Token::Hex(byte) => out.push_str(&format!("\\x{byte:02x}")),
+        }
+    }
+}
+
+// splits a string literal, quotes included, into the bytes it stands for
+pub fn tokenize(literal: &str) -> Result<Vec<Token>, LexError> {
+    let bytes = literal.as_bytes();
+    if bytes.len() < 2 || bytes[0] != b'"' || bytes[bytes.len() - 1] != b'"' {
+        return Err(LexError::MissingQuotes);
+    }
+    let end = bytes.len() - 1;
+    let mut tokens = vec![];
+    let mut i = 1;
+    while i < end {
+        let token = match bytes[i] {
+            b'"' => return Err(LexError::UnescapedQuote(i)),
+            b'\\' => match bytes.get(i + 1) {
+                Some(&escaped @ (b'\\' | b'"')) if i + 1 < end => Token::Escaped(escaped),
+                Some(b'x') if i + 3 < end => {
+                    let hex = &bytes[i + 2..i + 4];
+                    if !hex.iter().all(u8::is_ascii_hexdigit) {
+                        return Err(LexError::InvalidEscape(i));
+                    }
+                    let hex = std::str::from_utf8(hex).unwrap();
+                    Token::Hex(u8::from_str_radix(hex, 16).unwrap())
+                }
+                Some(b'x' | b'\\' | b'"') => return Err(LexError::UnterminatedEscape(i)),
+                _ if i + 1 == end => return Err(LexError::UnterminatedEscape(i)),
+                _ => return Err(LexError::InvalidEscape(i)),
+            },
+            byte => Token::Literal(byte),
+        };
+        i += token.code_len();
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+pub fn decode(literal: &str) -> Result<Vec<u8>, LexError> {
+    Ok(tokenize(literal)?.into_iter().map(Token::byte).collect())
+}
+
+// the string literal for `bytes`, which `decode` turns back into them
+pub fn encode(bytes: &[u8]) -> String {
+    let mut literal = String::from("\"");
+    for &byte in bytes {
+        Token::from_byte(byte).write(&mut literal);
+    }
+    literal.push('"');
+    literal
+}
+
+fn encoded_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .map(|&byte| Token::from_byte(byte).code_len())
+        .sum::<usize>()
+        + 2
+}
+
 pub fn count_repr_chars(s: &str) -> usize {
-    format!("{:?}", s).len()
+    encoded_len(s.as_bytes())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LineReport {
+    // characters of the literal as written
+    pub code: usize,
+    // bytes of the string it stands for
+    pub memory: usize,
+    // characters of the literal written as a literal itself
+    pub encoded: usize,
+}
+
+impl std::ops::Add for LineReport {
+    type Output = LineReport;
+
+    fn add(self, other: LineReport) -> LineReport {
+        LineReport {
+            code: self.code + other.code,
+            memory: self.memory + other.memory,
+            encoded: self.encoded + other.encoded,
+        }
+    }
+}
+
+impl std::iter::Sum for LineReport {
+    fn sum<I: Iterator<Item = LineReport>>(iter: I) -> LineReport {
+        iter.fold(LineReport::default(), |total, report| total + report)
+    }
+}
+
+pub fn report(literal: &str) -> Result<LineReport, LexError> {
+    Ok(LineReport {
+        code: literal.len(),
+        memory: tokenize(literal)?.len(),
+        encoded: count_repr_chars(literal),
+    })
+}
+
+pub fn report_lines<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<LineReport>, ReportError> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(line_number, line)| {
+            report(line).map_err(|source| ReportError {
+                line_number,
+                source,
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -10,4 +184,56 @@ mod tests {
     fn count_repr_chars_test_1() {
         assert_eq!(count_repr_chars("\"ab\\\\\""), 12);
     }
+
+    #[test]
+    fn decode_test_1() {
+        assert_eq!(decode(r#""""#).unwrap(), b"");
+        assert_eq!(decode(r#""abc""#).unwrap(), b"abc");
+        assert_eq!(decode(r#""aaa\"aaa""#).unwrap(), b"aaa\"aaa");
+        assert_eq!(decode(r#""\x27""#).unwrap(), b"'");
+        assert_eq!(decode(r#""\\\xfF\\x""#).unwrap(), b"\\\xff\\x");
+    }
+
+    #[test]
+    fn decode_test_2() {
+        assert_eq!(decode("abc"), Err(LexError::MissingQuotes));
+        assert_eq!(decode("\""), Err(LexError::MissingQuotes));
+        assert_eq!(decode(r#""a"b""#), Err(LexError::UnescapedQuote(2)));
+        assert_eq!(decode(r#""ab\""#), Err(LexError::UnterminatedEscape(3)));
+        assert_eq!(decode(r#""ab\x1""#), Err(LexError::UnterminatedEscape(3)));
+        assert_eq!(decode(r#""\n""#), Err(LexError::InvalidEscape(1)));
+        assert_eq!(decode(r#""\xg0""#), Err(LexError::InvalidEscape(1)));
+        assert_eq!(decode(r#""\x+f""#), Err(LexError::InvalidEscape(1)));
+    }
+
+    #[test]
+    fn encode_test_1() {
+        // examples from part 2
+        assert_eq!(encode(br#""""#), r#""\"\"""#);
+        assert_eq!(encode(br#""aaa\"aaa""#), r#""\"aaa\\\"aaa\"""#);
+        assert_eq!(encode(br#""\x27""#), r#""\"\\x27\"""#);
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(decode(&encode(&bytes)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn report_lines_test_1() {
+        let lines = [r#""""#, r#""abc""#, r#""aaa\"aaa""#, r#""\x27""#];
+        let total = report_lines(lines).unwrap().into_iter().sum::<LineReport>();
+        assert_eq!(
+            total,
+            LineReport {
+                code: 23,
+                memory: 11,
+                encoded: 42
+            }
+        );
+        assert_eq!(
+            report_lines([r#""ok""#, r#""\q""#]),
+            Err(ReportError {
+                line_number: 1,
+                source: LexError::InvalidEscape(1)
+            })
+        );
+    }
 }
@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write;
 
 use sscanf::sscanf;
+use thiserror::Error;
 
 // value => initial bot
 type Initial = (u32, u32);
@@ -195,6 +197,213 @@ pub fn get_outputs(init: &[Initial], transfers: &Transfers, outputs: &[u32]) ->
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    Comparison { bot: u32, low: u32, high: u32 },
+    Deliver { output: u32, value: u32 },
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum SimulationError {
+    #[error("bot {bot} is holding {count} chips")]
+    Overloaded { bot: u32, count: usize },
+    #[error("missing transfer instruction for bot {bot}")]
+    MissingTransfer { bot: u32 },
+    #[error("chip {value} is handed around in a cycle through bots {bots:?}")]
+    Cycle { value: u32, bots: Vec<u32> },
+    #[error("bots {bots:?} are stuck holding a single chip")]
+    Stalled { bots: Vec<u32> },
+}
+
+// output number => chips in the order they arrived
+pub type OutputBins = BTreeMap<u32, Vec<u32>>;
+
+#[derive(Debug)]
+pub struct Factory<'a> {
+    transfers: &'a Transfers,
+    bots: Bots,
+    outputs: OutputBins,
+    ready: VecDeque<u32>,
+    events: VecDeque<Event>,
+    // chip value => bots it has passed through
+    trails: HashMap<u32, Vec<u32>>,
+    failed: bool,
+}
+
+impl<'a> Factory<'a> {
+    pub fn new(
+        initial: &[Initial],
+        transfers: &'a Transfers,
+    ) -> Result<Factory<'a>, SimulationError> {
+        let mut factory = Factory {
+            transfers,
+            bots: Bots::new(),
+            outputs: OutputBins::new(),
+            ready: VecDeque::new(),
+            events: VecDeque::new(),
+            trails: HashMap::new(),
+            failed: false,
+        };
+        for &(value, bot) in initial {
+            factory.give_to_bot(bot, value)?;
+        }
+        Ok(factory)
+    }
+
+    pub fn outputs(&self) -> &OutputBins {
+        &self.outputs
+    }
+
+    fn give_to_bot(&mut self, bot: u32, value: u32) -> Result<(), SimulationError> {
+        let trail = self.trails.entry(value).or_default();
+        if let Some(pos) = trail.iter().position(|&b| b == bot) {
+            return Err(SimulationError::Cycle {
+                value,
+                bots: trail[pos..].to_vec(),
+            });
+        }
+        trail.push(bot);
+        let holder = self.bots.entry(bot).or_default();
+        holder.give(value);
+        match holder.numbers().len() {
+            2 => self.ready.push_back(bot),
+            count if count > 2 => return Err(SimulationError::Overloaded { bot, count }),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn deliver(&mut self, destination: &Destination, value: u32) -> Result<(), SimulationError> {
+        match *destination {
+            Destination::Bot(bot) => self.give_to_bot(bot, value),
+            Destination::Output(output) => {
+                self.outputs.entry(output).or_default().push(value);
+                self.events.push_back(Event::Deliver { output, value });
+                Ok(())
+            }
+        }
+    }
+
+    fn fire(&mut self, bot: u32) -> Result<(), SimulationError> {
+        let (low_dest, high_dest) = self
+            .transfers
+            .get(&bot)
+            .ok_or(SimulationError::MissingTransfer { bot })?;
+        let holder = self.bots.get_mut(&bot).expect("ready bot should exist");
+        let (low, high) = (holder.numbers[0], holder.numbers[1]);
+        holder.numbers.clear();
+        self.events.push_back(Event::Comparison { bot, low, high });
+        self.deliver(low_dest, low)?;
+        self.deliver(high_dest, high)
+    }
+
+    pub fn run(mut self) -> Result<(Vec<Event>, OutputBins), SimulationError> {
+        let events = self.by_ref().collect::<Result<Vec<_>, _>>()?;
+        Ok((events, self.outputs))
+    }
+}
+
+impl Iterator for Factory<'_> {
+    type Item = Result<Event, SimulationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.failed {
+                return None;
+            }
+            let Some(bot) = self.ready.pop_front() else {
+                // nothing left to fire, so chips still held can never move
+                let mut stalled = self
+                    .bots
+                    .iter()
+                    .filter(|(_, holder)| !holder.numbers().is_empty())
+                    .map(|(&bot, _)| bot)
+                    .collect::<Vec<_>>();
+                if stalled.is_empty() {
+                    return None;
+                }
+                stalled.sort();
+                self.failed = true;
+                return Some(Err(SimulationError::Stalled { bots: stalled }));
+            };
+            if let Err(err) = self.fire(bot) {
+                self.failed = true;
+                self.events.clear();
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+impl Destination {
+    fn node_name(&self) -> String {
+        match self {
+            Destination::Bot(bot) => format!("bot{bot}"),
+            Destination::Output(output) => format!("output{output}"),
+        }
+    }
+}
+
+pub fn to_dot(transfers: &Transfers, events: Option<&[Event]>) -> String {
+    // bot => (low, high) that it compared
+    let compared: HashMap<u32, (u32, u32)> = events
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|event| match *event {
+            Event::Comparison { bot, low, high } => Some((bot, (low, high))),
+            Event::Deliver { .. } => None,
+        })
+        .collect();
+    let mut bots = transfers.keys().copied().collect::<Vec<_>>();
+    bots.sort();
+    let mut outputs = transfers
+        .values()
+        .flat_map(|(low, high)| [low, high])
+        .filter_map(|dest| match dest {
+            Destination::Output(output) => Some(*output),
+            Destination::Bot(_) => None,
+        })
+        .collect::<Vec<_>>();
+    outputs.sort();
+    outputs.dedup();
+
+    let mut dot = String::from("digraph bots {\n");
+    for bot in &bots {
+        writeln!(dot, "    bot{bot} [label=\"bot {bot}\"];").unwrap();
+    }
+    for output in &outputs {
+        writeln!(
+            dot,
+            "    output{output} [label=\"output {output}\", shape=box];"
+        )
+        .unwrap();
+    }
+    for bot in &bots {
+        let (low, high) = &transfers[bot];
+        let (low_value, high_value) = match compared.get(bot) {
+            Some((low, high)) => (format!(": {low}"), format!(": {high}")),
+            None => (String::new(), String::new()),
+        };
+        writeln!(
+            dot,
+            "    bot{bot} -> {} [label=\"low{low_value}\"];",
+            low.node_name()
+        )
+        .unwrap();
+        writeln!(
+            dot,
+            "    bot{bot} -> {} [label=\"high{high_value}\"];",
+            high.node_name()
+        )
+        .unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,4 +510,133 @@ mod tests {
         let initial = set_up_bots(&initials);
         assert_eq!(find_bot(&initial, 8, 4), None);
     }
+
+    #[test]
+    fn factory_test_1() {
+        let lines = [
+            "value 5 goes to bot 2",
+            "bot 2 gives low to bot 1 and high to bot 0",
+            "value 3 goes to bot 1",
+            "bot 1 gives low to output 1 and high to bot 0",
+            "bot 0 gives low to output 2 and high to output 0",
+            "value 2 goes to bot 2",
+        ];
+        let (initials, transfers) = parse_instructions(&lines).unwrap();
+        let (events, outputs) = Factory::new(&initials, &transfers).unwrap().run().unwrap();
+        let correct = vec![
+            Event::Comparison {
+                bot: 2,
+                low: 2,
+                high: 5,
+            },
+            Event::Comparison {
+                bot: 1,
+                low: 2,
+                high: 3,
+            },
+            Event::Deliver {
+                output: 1,
+                value: 2,
+            },
+            Event::Comparison {
+                bot: 0,
+                low: 3,
+                high: 5,
+            },
+            Event::Deliver {
+                output: 2,
+                value: 3,
+            },
+            Event::Deliver {
+                output: 0,
+                value: 5,
+            },
+        ];
+        assert_eq!(events, correct);
+        let bins = OutputBins::from_iter([(0, vec![5]), (1, vec![2]), (2, vec![3])]);
+        assert_eq!(outputs, bins);
+    }
+
+    #[test]
+    fn factory_test_2() {
+        let initials = [(1, 4), (2, 4), (3, 4)];
+        let transfers = Transfers::new();
+        assert_eq!(
+            Factory::new(&initials, &transfers).unwrap_err(),
+            SimulationError::Overloaded { bot: 4, count: 3 }
+        );
+    }
+
+    #[test]
+    fn factory_test_3() {
+        let initials = [(1, 0), (2, 0)];
+        let transfers = Transfers::from_iter([(0, (Destination::Bot(1), Destination::Output(0)))]);
+        let mut factory = Factory::new(&initials, &transfers).unwrap();
+        factory.by_ref().for_each(drop);
+        assert_eq!(factory.outputs(), &OutputBins::from_iter([(0, vec![2])]));
+        let result = Factory::new(&initials, &transfers).unwrap().run();
+        assert_eq!(result, Err(SimulationError::Stalled { bots: vec![1] }));
+        let transfers = Transfers::new();
+        let result = Factory::new(&initials, &transfers).unwrap().run();
+        assert_eq!(result, Err(SimulationError::MissingTransfer { bot: 0 }));
+    }
+
+    #[test]
+    fn factory_test_4() {
+        let initials = [(1, 0), (2, 0), (3, 1)];
+        let transfers = Transfers::from_iter([
+            (0, (Destination::Bot(1), Destination::Output(0))),
+            (1, (Destination::Bot(0), Destination::Output(1))),
+        ]);
+        let result = Factory::new(&initials, &transfers).unwrap().run();
+        assert_eq!(
+            result,
+            Err(SimulationError::Cycle {
+                value: 1,
+                bots: vec![0, 1]
+            })
+        );
+    }
+
+    #[test]
+    fn to_dot_test_1() {
+        let transfers = Transfers::from_iter([
+            (2, (Destination::Bot(1), Destination::Output(0))),
+            (1, (Destination::Output(1), Destination::Output(0))),
+        ]);
+        let correct = "digraph bots {
+    bot1 [label=\"bot 1\"];
+    bot2 [label=\"bot 2\"];
+    output0 [label=\"output 0\", shape=box];
+    output1 [label=\"output 1\", shape=box];
+    bot1 -> output1 [label=\"low\"];
+    bot1 -> output0 [label=\"high\"];
+    bot2 -> bot1 [label=\"low\"];
+    bot2 -> output0 [label=\"high\"];
+}
+";
+        assert_eq!(to_dot(&transfers, None), correct);
+    }
+
+    #[test]
+    fn to_dot_test_2() {
+        let transfers = Transfers::from_iter([
+            (2, (Destination::Bot(1), Destination::Output(0))),
+            (1, (Destination::Output(1), Destination::Output(0))),
+        ]);
+        let initials = [(3, 2), (7, 2), (5, 1)];
+        let (events, _) = Factory::new(&initials, &transfers).unwrap().run().unwrap();
+        let correct = "digraph bots {
+    bot1 [label=\"bot 1\"];
+    bot2 [label=\"bot 2\"];
+    output0 [label=\"output 0\", shape=box];
+    output1 [label=\"output 1\", shape=box];
+    bot1 -> output1 [label=\"low: 3\"];
+    bot1 -> output0 [label=\"high: 5\"];
+    bot2 -> bot1 [label=\"low: 3\"];
+    bot2 -> output0 [label=\"high: 7\"];
+}
+";
+        assert_eq!(to_dot(&transfers, Some(&events)), correct);
+    }
 }
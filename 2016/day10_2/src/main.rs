@@ -1,12 +1,30 @@
-use std::fs::read_to_string;
+use std::{env, fs::read_to_string, process};
 
-use day10_2::{get_outputs, parse_instructions};
+use day10_2::{Factory, parse_instructions, to_dot};
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    process::exit(1);
+}
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
     let input = input.trim().lines().collect::<Vec<_>>();
     let (initial, transfers) = parse_instructions(&input).unwrap();
-    let outputs = get_outputs(&initial, &transfers, &[0, 1, 2]);
-    let product = outputs.iter().product::<u32>();
+    let (events, outputs) = match Factory::new(&initial, &transfers).and_then(Factory::run) {
+        Ok(result) => result,
+        Err(err) => fail(&err.to_string()),
+    };
+    if env::args().any(|arg| arg == "--dot") {
+        print!("{}", to_dot(&transfers, Some(&events)));
+        return;
+    }
+    let product = [0, 1, 2]
+        .iter()
+        .map(|out| match outputs.get(out) {
+            Some(bin) => bin.iter().product::<u32>(),
+            None => fail(&format!("output {out} never received a chip")),
+        })
+        .product::<u32>();
     println!("The product is: {product}");
 }
@@ -1,7 +1,19 @@
+use std::collections::VecDeque;
 use std::num::NonZero;
 
-fn contains_triplet(hash: &str) -> Option<char> {
-    for triple in hash.as_bytes().windows(3) {
+use hash_mining::{compute, hex_char, scan_nonces, to_hex, worker_count};
+
+const LOOKAHEAD: usize = 1000;
+const BATCH_SIZE: u64 = 256;
+
+type Hash = [u8; 32];
+
+fn hex_value(c: u8) -> u8 {
+    (c as char).to_digit(16).expect("hash should be hex") as u8
+}
+
+fn contains_triplet(hash: &[u8]) -> Option<char> {
+    for triple in hash.windows(3) {
         if triple[0] == triple[1] && triple[1] == triple[2] {
             return Some(triple[0] as char);
         }
@@ -9,48 +21,92 @@ fn contains_triplet(hash: &str) -> Option<char> {
     None
 }
 
-fn contains_five_chars(hash: &str, target: char) -> bool {
-    for window in hash.as_bytes().windows(5) {
-        if window.iter().all(|&c| c as char == target) {
-            return true;
+// bit i is set if hex digit i appears five times in a row
+fn quintuplet_set(hash: &[u8]) -> u16 {
+    let mut set = 0;
+    for window in hash.windows(5) {
+        if window.iter().all(|&c| c == window[0]) {
+            set |= 1 << hex_value(window[0]);
         }
     }
-    false
+    set
 }
 
-fn is_key_index(salt: &str, index: u64, computed: &mut Vec<String>) -> bool {
-    let composite = format!("{salt}{index}");
-    let hash_string = format!("{:x}", md5::compute(composite));
-    if let Some(c) = contains_triplet(&hash_string) {
-        for i in index + 1..index + 1001 {
-            while computed.get(i as usize).is_none() {
-                let composite = format!("{salt}{}", computed.len());
-                let hash = format!("{:x}", md5::compute(composite));
-                computed.push(hash);
-            }
-            let hash = &computed[i as usize];
-            if contains_five_chars(hash, c) {
-                return true;
-            }
+fn hash(str_to_hash: &[u8]) -> Hash {
+    to_hex(&compute(str_to_hash))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct HashInfo {
+    triplet: Option<u8>,
+    quintuplets: u16,
+}
+
+impl HashInfo {
+    fn new(hash: &Hash) -> Self {
+        HashInfo {
+            triplet: contains_triplet(hash).map(|c| hex_value(c as u8)),
+            quintuplets: quintuplet_set(hash),
         }
     }
-    false
 }
 
-pub fn index_of_nth_key(n: NonZero<u64>, salt: &str) -> u64 {
-    let n: u64 = n.into();
-    let mut index = 0;
-    let mut found = 0;
-    let mut computed = vec![];
-    loop {
-        if is_key_index(salt, index, &mut computed) {
-            found += 1;
+#[derive(Debug)]
+pub struct Keys {
+    salt: String,
+    // hashes for indices starting at `index`
+    window: VecDeque<HashInfo>,
+    index: u64,
+}
+
+impl Keys {
+    pub fn new(salt: &str) -> Self {
+        Keys {
+            salt: salt.to_owned(),
+            window: VecDeque::with_capacity(LOOKAHEAD + 1 + BATCH_SIZE as usize),
+            index: 0,
         }
-        if found == n {
-            break;
+    }
+
+    fn fill_window(&mut self) {
+        while self.window.len() <= LOOKAHEAD {
+            let start = self.index + self.window.len() as u64;
+            self.window.extend(scan_nonces(
+                self.salt.as_bytes(),
+                start..start + BATCH_SIZE,
+                worker_count(),
+                |_, message| Some(HashInfo::new(&hash(message))),
+            ));
+        }
+    }
+}
+
+impl Iterator for Keys {
+    type Item = (u64, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.fill_window();
+            let info = self.window.pop_front()?;
+            let index = self.index;
+            self.index += 1;
+            if let Some(c) = info.triplet
+                && self
+                    .window
+                    .iter()
+                    .take(LOOKAHEAD)
+                    .any(|next| next.quintuplets & (1 << c) != 0)
+            {
+                return Some((index, hex_char(c)));
+            }
         }
-        index += 1;
     }
+}
+
+pub fn index_of_nth_key(n: NonZero<u64>, salt: &str) -> u64 {
+    let (index, _) = Keys::new(salt)
+        .nth(n.get() as usize - 1)
+        .expect("keys never run out");
     index
 }
 
@@ -60,37 +116,36 @@ mod tests {
 
     #[test]
     fn contains_triplet_test_1() {
-        assert_eq!(contains_triplet("vdufnvudfniusdfnjnafsdfp"), None);
+        assert_eq!(contains_triplet(b"vdufnvudfniusdfnjnafsdfp"), None);
     }
 
     #[test]
     fn contains_triplet_test_2() {
-        assert_eq!(contains_triplet("vdfvnu3n45334cnasdj23222sad"), Some('2'));
+        assert_eq!(contains_triplet(b"vdfvnu3n45334cnasdj23222sad"), Some('2'));
     }
 
     #[test]
     fn contains_triplet_test_3() {
-        assert_eq!(contains_triplet("62m5694mmmk45ml6km4555fdasw"), Some('m'));
-    }
-
-    #[test]
-    fn contains_five_chars_test_1() {
-        assert!(contains_five_chars("adfvnmfffffsdunvb", 'f'));
+        assert_eq!(contains_triplet(b"62m5694mmmk45ml6km4555fdasw"), Some('m'));
     }
 
     #[test]
-    fn contains_five_chars_test_2() {
-        assert!(!contains_five_chars("adfvnmfffffsdunvb", 'p'));
+    fn quintuplet_set_test_1() {
+        assert_eq!(quintuplet_set(b"adf0e4bfffffdd3aa"), 1 << 0xf);
     }
 
     #[test]
-    fn is_key_index_test_1() {
-        assert!(is_key_index("abc", 39, &mut vec![]));
+    fn quintuplet_set_test_2() {
+        assert_eq!(quintuplet_set(b"0000077777e24ffff"), 1 << 0x0 | 1 << 0x7);
+        assert_eq!(quintuplet_set(b"adf0e4bffffdd3aa"), 0);
     }
 
     #[test]
-    fn is_key_index_test_2() {
-        assert!(!is_key_index("abc", 18, &mut vec![]));
+    fn keys_test_1() {
+        let keys = Keys::new("abc")
+            .take_while(|&(index, _)| index <= 39)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [(39, 'e')]);
     }
 
     #[test]
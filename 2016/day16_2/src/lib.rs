@@ -1,39 +1,143 @@
-use itertools::Itertools;
-
-fn next_iter(initial: &str) -> String {
-    let b: String = initial
-        .chars()
-        .rev()
-        .map(|c| if c == '0' { '1' } else { '0' })
-        .collect();
-    format!("{initial}0{b}")
+// `a`, its reversed complement `b`, then a, b, ... joined by dragon curve separator bits
+#[derive(Debug, PartialEq, Clone)]
+pub struct Disk {
+    initial: Vec<bool>,
+    // prefix_ones[i] = number of ones among the first i bits of `initial`
+    prefix_ones: Vec<usize>,
 }
 
-pub fn generate_data(initial: &str, size: usize) -> String {
-    let mut current = initial.to_owned();
-    while current.len() < size {
-        current = next_iter(&current);
+// separator `n` (from 1) with n = m * 2^k, m odd: 0 when m % 4 == 1, else 1
+fn separator(n: usize) -> bool {
+    let odd = n >> n.trailing_zeros();
+    odd % 4 == 3
+}
+
+// number of ones among the first `count` separators
+fn separator_ones(count: usize) -> usize {
+    if count == 0 {
+        0
+    } else {
+        (count + 1) / 4 + separator_ones(count / 2)
     }
-    current.chars().take(size).collect()
+}
+
+impl Disk {
+    pub fn new(initial: &str) -> Self {
+        let initial: Vec<_> = initial.chars().map(|c| c == '1').collect();
+        let mut prefix_ones = vec![0];
+        for &bit in &initial {
+            prefix_ones.push(prefix_ones.last().unwrap() + bit as usize);
+        }
+        Disk {
+            initial,
+            prefix_ones,
+        }
+    }
+
+    fn period(&self) -> usize {
+        self.initial.len() + 1
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        let len = self.initial.len();
+        let (block, offset) = (index / self.period(), index % self.period());
+        if offset == len {
+            separator(block + 1)
+        } else if block.is_multiple_of(2) {
+            self.initial[offset]
+        } else {
+            !self.initial[len - 1 - offset]
+        }
+    }
+
+    // number of ones among the first `count` bits
+    pub fn ones(&self, count: usize) -> usize {
+        let len = self.initial.len();
+        let total_ones = self.prefix_ones[len];
+        let (blocks, offset) = (count / self.period(), count % self.period());
+        // each a/b pair contains exactly `len` ones
+        let mut ones = blocks / 2 * len + separator_ones(blocks);
+        if blocks % 2 == 1 {
+            ones += total_ones;
+        }
+        ones += if blocks.is_multiple_of(2) {
+            self.prefix_ones[offset.min(len)]
+        } else {
+            let taken = offset.min(len);
+            taken - (total_ones - self.prefix_ones[len - taken])
+        };
+        if offset > len {
+            ones += separator(blocks + 1) as usize;
+        }
+        ones
+    }
+
+    // each digit is 1 when its chunk (largest power of two dividing `size`) has even ones
+    pub fn checksum(&self, size: usize) -> String {
+        if size == 0 {
+            return String::new();
+        }
+        let chunk = 1 << size.trailing_zeros();
+        if chunk == 1 {
+            return (0..size)
+                .map(|i| if self.bit(i) { '1' } else { '0' })
+                .collect();
+        }
+        (0..size / chunk)
+            .map(|i| {
+                let ones = self.ones((i + 1) * chunk) - self.ones(i * chunk);
+                if ones.is_multiple_of(2) { '1' } else { '0' }
+            })
+            .collect()
+    }
+}
+
+pub fn generate_data(initial: &str, size: usize) -> String {
+    let disk = Disk::new(initial);
+    (0..size)
+        .map(|i| if disk.bit(i) { '1' } else { '0' })
+        .collect()
 }
 
 pub fn checksum(data: &str) -> String {
-    let mut data = data.to_owned();
-    while data.len() % 2 == 0 {
-        data = data
-            .chars()
-            .tuple_windows()
-            .step_by(2)
-            .map(|(first, second)| if first == second { '1' } else { '0' })
-            .collect();
+    let size = data.len();
+    if size == 0 {
+        return String::new();
     }
-    data
+    let chunk = 1 << size.trailing_zeros();
+    if chunk == 1 {
+        return data.to_owned();
+    }
+    data.as_bytes()
+        .chunks(chunk)
+        .map(|chunk| {
+            let ones = chunk.iter().filter(|&&c| c == b'1').count();
+            if ones.is_multiple_of(2) { '1' } else { '0' }
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn next_iter(initial: &str) -> String {
+        let b: String = initial
+            .chars()
+            .rev()
+            .map(|c| if c == '0' { '1' } else { '0' })
+            .collect();
+        format!("{initial}0{b}")
+    }
+
+    fn generate_data_by_doubling(initial: &str, size: usize) -> String {
+        let mut current = initial.to_owned();
+        while current.len() < size {
+            current = next_iter(&current);
+        }
+        current.chars().take(size).collect()
+    }
+
     #[test]
     fn next_iter_test_1() {
         assert_eq!(next_iter("1010"), "101001010");
@@ -49,6 +153,16 @@ mod tests {
         assert_eq!(generate_data("10", 15), "100100101100100")
     }
 
+    #[test]
+    fn generate_data_test_3() {
+        for initial in ["1", "0", "10000", "111100001010", "0110011"] {
+            assert_eq!(
+                generate_data(initial, 3000),
+                generate_data_by_doubling(initial, 3000)
+            );
+        }
+    }
+
     #[test]
     fn checksum_test_1() {
         assert_eq!(checksum(&generate_data("10", 16)), "0");
@@ -58,4 +172,31 @@ mod tests {
     fn checksum_test_2() {
         assert_eq!(checksum(&generate_data("10000", 20)), "01100");
     }
+
+    #[test]
+    fn checksum_test_3() {
+        assert_eq!(checksum("110010110100"), "100");
+        assert_eq!(checksum("10101"), "10101");
+    }
+
+    #[test]
+    fn disk_ones_test_1() {
+        let disk = Disk::new("10011");
+        let data = generate_data_by_doubling("10011", 2000);
+        for count in 0..=2000 {
+            let ones = data[..count].chars().filter(|&c| c == '1').count();
+            assert_eq!(disk.ones(count), ones);
+        }
+    }
+
+    #[test]
+    fn disk_checksum_test_1() {
+        assert_eq!(Disk::new("10000").checksum(20), "01100");
+        for initial in ["1", "10000", "111100001010"] {
+            let disk = Disk::new(initial);
+            for size in [1, 6, 272, 1000, 4096, 12345] {
+                assert_eq!(disk.checksum(size), checksum(&generate_data(initial, size)));
+            }
+        }
+    }
 }
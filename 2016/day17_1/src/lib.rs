@@ -1,3 +1,5 @@
+use hash_mining::{Digest, compute_all, nibble};
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Move {
     Up,
@@ -28,17 +30,25 @@ struct OpenDoors {
 }
 
 impl OpenDoors {
-    pub fn new(salt: &str, moves: &[Move]) -> Self {
-        let hash_str = format!("{salt}{}", moves_to_string(moves));
-        let hash = format!("{:x}", md5::compute(&hash_str));
-        let chars: Vec<_> = hash.chars().collect();
+    fn from_digest(digest: &Digest) -> Self {
         OpenDoors {
-            up: chars[0] >= 'b',
-            down: chars[1] >= 'b',
-            left: chars[2] >= 'b',
-            right: chars[3] >= 'b',
+            up: nibble(digest, 0) >= 0xb,
+            down: nibble(digest, 1) >= 0xb,
+            left: nibble(digest, 2) >= 0xb,
+            right: nibble(digest, 3) >= 0xb,
         }
     }
+
+    fn for_paths(salt: &str, horizon: &[Path]) -> Vec<Self> {
+        let inputs = horizon
+            .iter()
+            .map(|(_, _, moves)| format!("{salt}{}", moves_to_string(moves)))
+            .collect::<Vec<_>>();
+        compute_all(&inputs)
+            .iter()
+            .map(OpenDoors::from_digest)
+            .collect()
+    }
 }
 
 type Path = (u8, u8, Vec<Move>);
@@ -50,11 +60,11 @@ pub fn shortest_path(salt: &str) -> Option<Vec<Move>> {
             return None;
         }
         let mut new_horizon = vec![];
-        for (row, col, moves) in horizon {
+        let doors = OpenDoors::for_paths(salt, &horizon);
+        for ((row, col, moves), open_doors) in horizon.into_iter().zip(doors) {
             if row == 3 && col == 3 {
                 return Some(moves);
             }
-            let open_doors = OpenDoors::new(salt, &moves);
             if row > 0 && open_doors.up {
                 let mut new_moves = moves.clone();
                 new_moves.push(Move::Up);
@@ -83,11 +93,12 @@ pub fn shortest_path(salt: &str) -> Option<Vec<Move>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use hash_mining::compute;
 
     #[test]
-    fn open_doors_new_test_1() {
+    fn open_doors_from_digest_test_1() {
         assert_eq!(
-            OpenDoors::new("hijkl", &[]),
+            OpenDoors::from_digest(&compute("hijkl")),
             OpenDoors {
                 up: true,
                 down: true,
@@ -56,6 +56,158 @@ pub fn count_safe(floor: &Floor) -> usize {
         .sum()
 }
 
+#[derive(Debug, PartialEq)]
+pub enum PackedRowError {
+    InvalidCharacter(char),
+    TooWide { width: usize },
+}
+
+// bit i is set when tile i is a trap
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct PackedRow {
+    bits: u128,
+    width: u32,
+}
+
+impl PackedRow {
+    pub fn from_tiles(tiles: &[Tile]) -> Result<Self, PackedRowError> {
+        if tiles.len() > u128::BITS as usize {
+            return Err(PackedRowError::TooWide { width: tiles.len() });
+        }
+        let bits = tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| matches!(tile, Tile::Trap))
+            .fold(0, |bits, (i, _)| bits | 1 << i);
+        Ok(PackedRow {
+            bits,
+            width: tiles.len() as u32,
+        })
+    }
+
+    pub fn parse(line: &str) -> Result<Self, PackedRowError> {
+        let tiles = line
+            .chars()
+            .map(|c| match c {
+                '^' => Ok(Tile::Trap),
+                '.' => Ok(Tile::Safe),
+                _ => Err(PackedRowError::InvalidCharacter(c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        PackedRow::from_tiles(&tiles)
+    }
+
+    fn mask(&self) -> u128 {
+        u128::MAX.checked_shr(u128::BITS - self.width).unwrap_or(0)
+    }
+
+    // a trap when exactly one diagonal parent is a trap, walls counting as safe
+    pub fn next(&self) -> Self {
+        PackedRow {
+            bits: (self.bits << 1 ^ self.bits >> 1) & self.mask(),
+            width: self.width,
+        }
+    }
+
+    // tile j is at j + 1 on a ring of 2 * (width + 1) mirrored around the walls
+    fn mirrored(&self, position: u64) -> bool {
+        let width = self.width as u64;
+        let period = 2 * (width + 1);
+        let position = position % period;
+        let position = position.min(period - position);
+        position != 0 && position <= width && self.bits >> (position - 1) & 1 == 1
+    }
+
+    // the row 2^k below, `shift` being 2^k modulo the ring size
+    fn skip_power_of_two(&self, shift: u64) -> Self {
+        let period = 2 * (self.width as u64 + 1);
+        let bits = (0..self.width as u64)
+            .filter(|&j| self.mirrored(j + 1 + period - shift) ^ self.mirrored(j + 1 + shift))
+            .fold(0, |bits, j| bits | 1 << j);
+        PackedRow {
+            bits,
+            width: self.width,
+        }
+    }
+
+    // the row `steps` rows below, in O(width * log steps)
+    pub fn advance(&self, mut steps: u64) -> Self {
+        let period = 2 * (self.width as u64 + 1);
+        let mut row = *self;
+        let mut shift = 1 % period;
+        while steps > 0 {
+            if steps & 1 == 1 {
+                row = row.skip_power_of_two(shift);
+            }
+            steps >>= 1;
+            shift = shift * 2 % period;
+        }
+        row
+    }
+
+    pub fn safe_count(&self) -> u64 {
+        (self.width - self.bits.count_ones()) as u64
+    }
+
+    pub fn render(&self) -> String {
+        (0..self.width)
+            .map(|i| if self.bits >> i & 1 == 1 { '^' } else { '.' })
+            .collect()
+    }
+}
+
+pub fn packed_rows(first: PackedRow) -> impl Iterator<Item = PackedRow> {
+    std::iter::successors(Some(first), |row| Some(row.next()))
+}
+
+pub fn render_floor(first: PackedRow, total_rows: usize) -> String {
+    packed_rows(first)
+        .take(total_rows)
+        .map(|row| row.render() + "\n")
+        .collect()
+}
+
+fn safe_in_rows(first: PackedRow, rows: u64) -> u64 {
+    packed_rows(first)
+        .take(rows as usize)
+        .map(|row| row.safe_count())
+        .sum()
+}
+
+// counts rows until Brent's algorithm finds a cycle, then multiplies it out
+pub fn count_safe_packed(first: PackedRow, total_rows: u64) -> u64 {
+    if total_rows == 0 {
+        return 0;
+    }
+    // safe tiles in rows 0..=index
+    let mut safe = first.safe_count();
+    let mut index = 0;
+    let mut tortoise = first;
+    let mut hare = first;
+    let mut power = 1;
+    let mut cycle_len = 0;
+    loop {
+        if index + 1 == total_rows {
+            return safe;
+        }
+        hare = hare.next();
+        index += 1;
+        cycle_len += 1;
+        if hare == tortoise {
+            break;
+        }
+        safe += hare.safe_count();
+        if cycle_len == power {
+            tortoise = hare;
+            power *= 2;
+            cycle_len = 0;
+        }
+    }
+    // rows from `index` on repeat the `cycle_len` rows starting at `hare`
+    let left = total_rows - index;
+    safe + left / cycle_len * safe_in_rows(hare, cycle_len) + safe_in_rows(hare, left % cycle_len)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,4 +262,105 @@ mod tests {
         let line = "..^.^";
         assert_eq!(parse_row(line).unwrap(), vec![Safe, Safe, Trap, Safe, Trap]);
     }
+
+    #[test]
+    fn packed_row_test_1() {
+        let row = PackedRow::parse(".^^.^.^^^^").unwrap();
+        assert_eq!(row.render(), ".^^.^.^^^^");
+        assert_eq!(row.next().render(), "^^^...^..^");
+        assert_eq!(row.safe_count(), 3);
+    }
+
+    #[test]
+    fn packed_row_test_2() {
+        assert_eq!(
+            PackedRow::parse("..x"),
+            Err(PackedRowError::InvalidCharacter('x'))
+        );
+        assert_eq!(
+            PackedRow::parse(&".".repeat(129)),
+            Err(PackedRowError::TooWide { width: 129 })
+        );
+        let wide = PackedRow::parse(&"^.".repeat(64)).unwrap();
+        let tiles = parse_row(&"^.".repeat(64)).unwrap();
+        assert_eq!(wide.next().render(), next_row_string(&tiles));
+    }
+
+    fn next_row_string(tiles: &[Tile]) -> String {
+        next_row(tiles)
+            .iter()
+            .map(|tile| match tile {
+                Tile::Safe => '.',
+                Tile::Trap => '^',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn packed_row_advance_test_1() {
+        for line in [
+            "^",
+            "..^^.",
+            ".^^.^.^^^^",
+            &"^^.^.".repeat(20),
+            &"^.".repeat(64),
+        ] {
+            let row = PackedRow::parse(line).unwrap();
+            for (steps, expected) in packed_rows(row).take(600).enumerate() {
+                assert_eq!(row.advance(steps as u64), expected);
+            }
+            let far = 1_000_000_000_000;
+            assert_eq!(row.advance(far).advance(777), row.advance(far + 777));
+        }
+    }
+
+    #[test]
+    fn render_floor_test_1() {
+        let row = PackedRow::parse("..^^.").unwrap();
+        assert_eq!(render_floor(row, 3), "..^^.\n.^^^^\n^^..^\n");
+    }
+
+    #[test]
+    fn count_safe_packed_test_1() {
+        let row = PackedRow::parse(".^^.^.^^^^").unwrap();
+        assert_eq!(count_safe_packed(row, 10), 38);
+        assert_eq!(count_safe_packed(row, 0), 0);
+    }
+
+    #[test]
+    fn count_safe_packed_test_2() {
+        for line in [
+            "^",
+            "..^^.",
+            ".^^.^.^^^^",
+            "^^.^..^.^^^.^",
+            "..^^^^^^.^^.^^^.^^^..",
+        ] {
+            let tiles = parse_row(line).unwrap();
+            let floor = generate_floor(&tiles, 3000);
+            let row = PackedRow::parse(line).unwrap();
+            assert_eq!(count_safe_packed(row, 3000), count_safe(&floor) as u64);
+        }
+    }
+
+    #[test]
+    fn count_safe_packed_test_3() {
+        // an all-safe row stays safe forever
+        let row = PackedRow::parse("..........").unwrap();
+        assert_eq!(count_safe_packed(row, 5_000_000_000), 50_000_000_000);
+        let row = PackedRow::parse(".^^.^.^^^^").unwrap();
+        let start = 3000;
+        let start_row = packed_rows(row).nth(start).unwrap();
+        let cycle_len = packed_rows(start_row.next())
+            .position(|next| next == start_row)
+            .unwrap() as u64
+            + 1;
+        let before = count_safe_packed(row, start as u64);
+        let per_cycle = count_safe_packed(row, start as u64 + cycle_len) - before;
+        let cycles = 1_000_000_000;
+        assert_eq!(
+            count_safe_packed(row, start as u64 + cycles * cycle_len),
+            before + cycles * per_cycle
+        );
+    }
 }
@@ -1,11 +1,11 @@
 use std::fs::read_to_string;
 
-use day18_2::{count_safe, generate_floor, parse_row};
+use day18_2::{PackedRow, count_safe_packed};
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
     let input = input.trim().lines().collect::<Vec<_>>()[0];
-    let floor = generate_floor(&parse_row(input).unwrap(), 400000);
-    let count = count_safe(&floor);
+    let row = PackedRow::parse(input).unwrap();
+    let count = count_safe_packed(row, 400000);
     println!("{count} safe tiles");
 }
@@ -1,4 +1,4 @@
-use std::num::NonZero;
+use std::{collections::VecDeque, num::NonZero};
 
 #[derive(Debug, PartialEq)]
 pub struct ElfCircle {
@@ -53,6 +53,117 @@ pub fn last_elf(count: NonZero<usize>) -> usize {
     }
 }
 
+pub fn last_elf_left(count: NonZero<usize>) -> usize {
+    let mut circle: VecDeque<usize> = (1..=count.get()).collect();
+    while circle.len() > 1 {
+        let thief = circle.pop_front().unwrap();
+        circle.pop_front();
+        circle.push_back(thief);
+    }
+    circle[0]
+}
+
+// halves keep the elf across from the thief at the back of `left` or front of `right`
+pub fn last_elf_across(count: NonZero<usize>) -> usize {
+    let count = count.get();
+    let mut left: VecDeque<usize> = (1..=count / 2).collect();
+    let mut right: VecDeque<usize> = (count / 2 + 1..=count).collect();
+    while left.len() + right.len() > 1 {
+        if left.len() > right.len() {
+            left.pop_back();
+        } else {
+            right.pop_front();
+        }
+        if let Some(thief) = left.pop_front() {
+            right.push_back(thief);
+        }
+        if let Some(elf) = right.pop_front() {
+            left.push_back(elf);
+        }
+    }
+    left.front().or(right.front()).copied().unwrap()
+}
+
+// Josephus problem with every second elf removed
+pub fn last_elf_left_closed_form(count: NonZero<usize>) -> usize {
+    let count = count.get();
+    let highest_power = 1 << count.ilog2();
+    2 * (count - highest_power) + 1
+}
+
+pub fn last_elf_across_closed_form(count: NonZero<usize>) -> usize {
+    let count = count.get();
+    let power = 3_usize.pow(count.ilog(3));
+    if count == power {
+        count
+    } else if count <= 2 * power {
+        count - power
+    } else {
+        2 * count - 3 * power
+    }
+}
+
+// Fenwick tree over elves 1..=n that are still in the circle
+#[derive(Debug)]
+struct Remaining {
+    tree: Vec<usize>,
+}
+
+impl Remaining {
+    fn new(count: usize) -> Self {
+        let mut tree = vec![0; count + 1];
+        for i in 1..=count {
+            tree[i] += 1;
+            let parent = i + (i & i.wrapping_neg());
+            if parent <= count {
+                tree[parent] += tree[i];
+            }
+        }
+        Remaining { tree }
+    }
+
+    fn remove(&mut self, elf: usize) {
+        let mut i = elf;
+        while i < self.tree.len() {
+            self.tree[i] -= 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    // the elf with the given 0-based rank among the remaining ones
+    fn nth(&self, rank: usize) -> usize {
+        let mut pos = 0;
+        let mut left = rank + 1;
+        let mut step = 1 << (self.tree.len() - 1).ilog2();
+        while step > 0 {
+            if pos + step < self.tree.len() && self.tree[pos + step] < left {
+                pos += step;
+                left -= self.tree[pos];
+            }
+            step /= 2;
+        }
+        pos + 1
+    }
+}
+
+// `offset(elves_left)` is the victim's distance clockwise; `None` if an elf targets itself
+pub fn last_elf_by_rule(count: NonZero<usize>, offset: impl Fn(usize) -> usize) -> Option<usize> {
+    let mut remaining = Remaining::new(count.get());
+    let mut thief = 0;
+    for len in (2..=count.get()).rev() {
+        let target = (thief + offset(len)) % len;
+        if target == thief {
+            return None;
+        }
+        remaining.remove(remaining.nth(target));
+        if target < thief {
+            thief -= 1;
+        }
+        thief = (thief + 1) % (len - 1);
+    }
+    Some(remaining.nth(0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +239,89 @@ mod tests {
         );
         assert!(circle.try_steal(3).is_err());
     }
+
+    #[test]
+    fn last_elf_left_test_1() {
+        assert_eq!(last_elf_left(5.try_into().unwrap()), 3);
+        assert_eq!(last_elf_left_closed_form(5.try_into().unwrap()), 3);
+    }
+
+    #[test]
+    fn last_elf_across_test_1() {
+        assert_eq!(last_elf_across(5.try_into().unwrap()), 2);
+        assert_eq!(last_elf_across_closed_form(5.try_into().unwrap()), 2);
+    }
+
+    #[test]
+    fn remaining_test_1() {
+        let mut remaining = Remaining::new(6);
+        remaining.remove(2);
+        remaining.remove(5);
+        let elves = (0..4).map(|rank| remaining.nth(rank)).collect::<Vec<_>>();
+        assert_eq!(elves, [1, 3, 4, 6]);
+    }
+
+    // the original `ElfCircle` simulation with the victim chosen by `offset`
+    fn last_elf_by_circle(count: NonZero<usize>, offset: impl Fn(usize) -> usize) -> usize {
+        let mut circle = ElfCircle::new(count);
+        let mut current_thief = 0;
+        while circle.present.len() > 1 {
+            let len = circle.present.len();
+            let target = (current_thief + offset(len)) % len;
+            if target < current_thief {
+                current_thief -= 1;
+            }
+            circle.try_steal(target).unwrap();
+            current_thief = circle.next_unskipped(current_thief);
+        }
+        circle.present[0]
+    }
+
+    #[test]
+    fn last_elf_by_rule_test_1() {
+        for count in 1..=500 {
+            let count = count.try_into().unwrap();
+            assert_eq!(
+                last_elf_by_rule(count, |_| 1),
+                Some(last_elf_by_circle(count, |_| 1))
+            );
+            assert_eq!(
+                last_elf_by_rule(count, |len| len / 2),
+                Some(last_elf_by_circle(count, |len| len / 2))
+            );
+            assert_eq!(
+                last_elf_by_rule(count, |len| len - 1),
+                Some(last_elf_by_circle(count, |len| len - 1))
+            );
+        }
+    }
+
+    #[test]
+    fn last_elf_by_rule_test_2() {
+        assert_eq!(last_elf_by_rule(5.try_into().unwrap(), |len| len), None);
+        assert_eq!(last_elf_by_rule(5.try_into().unwrap(), |_| 3), None);
+        assert_eq!(last_elf_by_rule(1.try_into().unwrap(), |_| 0), Some(1));
+    }
+
+    #[test]
+    fn last_elf_left_agreement_test() {
+        for count in 1..=3000 {
+            let count = count.try_into().unwrap();
+            let expected = last_elf_by_rule(count, |_| 1).unwrap();
+            assert_eq!(last_elf_left(count), expected);
+            assert_eq!(last_elf_left_closed_form(count), expected);
+        }
+    }
+
+    #[test]
+    fn last_elf_across_agreement_test() {
+        let counts = (1..=500).chain([728, 729, 730, 1458, 1459, 2186, 2187, 3000]);
+        for count in counts {
+            let count = count.try_into().unwrap();
+            let expected = last_elf(count);
+            assert_eq!(last_elf_across(count), expected);
+            assert_eq!(last_elf_across_closed_form(count), expected);
+            assert_eq!(last_elf_by_rule(count, |len| len / 2), Some(expected));
+        }
+    }
 }
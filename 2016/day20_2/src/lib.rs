@@ -1,6 +1,6 @@
-use std::{collections::HashSet, ops::RangeInclusive};
+use std::{net::Ipv4Addr, ops::RangeInclusive};
 
-use sscanf::sscanf;
+use thiserror::Error;
 
 type IpRange = RangeInclusive<u32>;
 
@@ -20,42 +20,214 @@ fn try_combine(range_1: &IpRange, range_2: &IpRange) -> Option<IpRange> {
     None
 }
 
-pub fn parse_ranges(lines: &[&str]) -> Result<HashSet<IpRange>, sscanf::Error> {
-    let mut ranges: HashSet<IpRange> = HashSet::new();
-    for line in lines {
-        let (lower, upper) = sscanf!(line, "{u32}-{u32}")?;
-        let mut range = lower..=upper;
-        let mut absorbed = vec![];
-        for other_range in ranges.iter() {
-            if let Some(combined) = try_combine(&range, other_range) {
-                absorbed.push(other_range.clone());
-                range = combined;
+// sorted ranges, none of which overlap or touch each other
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct IntervalSet {
+    ranges: Vec<IpRange>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        IntervalSet::default()
+    }
+
+    pub fn full() -> Self {
+        IntervalSet {
+            ranges: vec![0..=u32::MAX],
+        }
+    }
+
+    pub fn ranges(&self) -> &[IpRange] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    // number of addresses in the set, which can be up to 2^32
+    pub fn size(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|range| *range.end() as u64 - *range.start() as u64 + 1)
+            .sum()
+    }
+
+    pub fn contains(&self, ip: u32) -> bool {
+        let index = self.ranges.partition_point(|range| *range.end() < ip);
+        self.ranges
+            .get(index)
+            .is_some_and(|range| range.contains(&ip))
+    }
+
+    pub fn insert(&mut self, range: IpRange) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self
+            .ranges
+            .partition_point(|other| other.end().saturating_add(1) < *range.start());
+        let mut last = first;
+        let mut merged = range;
+        while let Some(combined) = self
+            .ranges
+            .get(last)
+            .and_then(|other| try_combine(&merged, other))
+        {
+            merged = combined;
+            last += 1;
+        }
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn remove(&mut self, range: IpRange) {
+        if range.is_empty() {
+            return;
+        }
+        let (start, end) = (*range.start(), *range.end());
+        let mut remaining = Vec::with_capacity(self.ranges.len() + 1);
+        for other in self.ranges.drain(..) {
+            if *other.end() < start || *other.start() > end {
+                remaining.push(other);
+                continue;
             }
+            if *other.start() < start {
+                remaining.push(*other.start()..=start - 1);
+            }
+            if *other.end() > end {
+                remaining.push(end + 1..=*other.end());
+            }
+        }
+        self.ranges = remaining;
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut union = self.clone();
+        for range in &other.ranges {
+            union.insert(range.clone());
         }
-        for old_range in absorbed {
-            ranges.remove(&old_range);
+        union
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let start = *a.start().max(b.start());
+            let end = *a.end().min(b.end());
+            if start <= end {
+                ranges.push(start..=end);
+            }
+            if a.end() < b.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    // ranges of addresses that are not in the set
+    pub fn gaps(&self) -> impl Iterator<Item = IpRange> + '_ {
+        let starts = std::iter::once(Some(0))
+            .chain(self.ranges.iter().map(|range| range.end().checked_add(1)));
+        let ends = self
+            .ranges
+            .iter()
+            .map(|range| range.start().checked_sub(1))
+            .chain(std::iter::once(Some(u32::MAX)));
+        starts
+            .zip(ends)
+            .filter_map(|(start, end)| match (start, end) {
+                (Some(start), Some(end)) if start <= end => Some(start..=end),
+                _ => None,
+            })
+    }
+
+    pub fn complement(&self) -> IntervalSet {
+        IntervalSet {
+            ranges: self.gaps().collect(),
         }
-        ranges.insert(range);
     }
-    Ok(ranges)
 }
 
-pub fn lowest_allowed_ip(ranges: &HashSet<IpRange>) -> Option<u32> {
-    let lowest_range = ranges.iter().min_by_key(|range| range.start())?;
-    if lowest_range.end() == &u32::MAX {
-        None
-    } else {
-        Some(*lowest_range.end() + 1)
+impl FromIterator<IpRange> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = IpRange>>(iter: T) -> Self {
+        let mut set = IntervalSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
     }
 }
 
-pub fn count_allowed_ips(ranges: &HashSet<IpRange>) -> usize {
-    let mut allowed = u32::MAX as usize + 1;
-    for range in ranges {
-        let range_size = *range.end() as usize - *range.start() as usize + 1;
-        allowed -= range_size;
+#[derive(Debug, PartialEq, Error)]
+pub enum ParseRangeError {
+    #[error("line #{line_number} has an invalid address: {text}")]
+    InvalidAddress { line_number: usize, text: String },
+    #[error("line #{line_number} has an invalid prefix length: {text}")]
+    InvalidPrefix { line_number: usize, text: String },
+    #[error("line #{line_number} has a range that ends before it starts")]
+    ReversedRange { line_number: usize },
+}
+
+fn parse_ip(text: &str, line_number: usize) -> Result<u32, ParseRangeError> {
+    let text = text.trim();
+    let parsed = if text.contains('.') {
+        text.parse::<Ipv4Addr>().map(u32::from).ok()
+    } else {
+        text.parse::<u32>().ok()
+    };
+    parsed.ok_or_else(|| ParseRangeError::InvalidAddress {
+        line_number,
+        text: text.to_owned(),
+    })
+}
+
+// "lower-upper", "a.b.c.d/prefix" or a single address, each plain or dotted
+fn parse_range(line: &str, line_number: usize) -> Result<IpRange, ParseRangeError> {
+    if let Some((lower, upper)) = line.split_once('-') {
+        let lower = parse_ip(lower, line_number)?;
+        let upper = parse_ip(upper, line_number)?;
+        if upper < lower {
+            return Err(ParseRangeError::ReversedRange { line_number });
+        }
+        return Ok(lower..=upper);
     }
-    allowed
+    if let Some((base, prefix)) = line.split_once('/') {
+        let base = parse_ip(base, line_number)?;
+        let prefix = prefix
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|&prefix| prefix <= 32)
+            .ok_or_else(|| ParseRangeError::InvalidPrefix {
+                line_number,
+                text: prefix.to_owned(),
+            })?;
+        let host_mask = u32::MAX.checked_shr(prefix).unwrap_or(0);
+        let network = base & !host_mask;
+        return Ok(network..=network | host_mask);
+    }
+    let ip = parse_ip(line, line_number)?;
+    Ok(ip..=ip)
+}
+
+pub fn parse_ranges(lines: &[&str]) -> Result<IntervalSet, ParseRangeError> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(line_number, line)| parse_range(line, line_number))
+        .collect()
+}
+
+pub fn lowest_allowed_ip(ranges: &IntervalSet) -> Option<u32> {
+    ranges.gaps().next().map(|gap| *gap.start())
+}
+
+pub fn count_allowed_ips(ranges: &IntervalSet) -> usize {
+    (u32::MAX as u64 + 1 - ranges.size()) as usize
 }
 
 #[cfg(test)]
@@ -86,34 +258,129 @@ mod tests {
         assert_eq!(try_combine(&(11..=13), &(3..=14)), Some(3..=14));
     }
 
+    #[test]
+    fn interval_set_insert_test_1() {
+        let set = IntervalSet::from_iter([20..=25, 11..=36, 40..=50, 37..=38, 60..=60]);
+        assert_eq!(set.ranges(), [11..=38, 40..=50, 60..=60]);
+        let set = IntervalSet::from_iter([5..=9, 0..=3, u32::MAX..=u32::MAX, 4..=u32::MAX]);
+        assert_eq!(set, IntervalSet::full());
+    }
+
+    #[test]
+    fn interval_set_remove_test_1() {
+        let mut set = IntervalSet::from_iter([0..=10, 20..=30, 40..=50]);
+        set.remove(5..=25);
+        assert_eq!(set.ranges(), [0..=4, 26..=30, 40..=50]);
+        set.remove(42..=43);
+        assert_eq!(set.ranges(), [0..=4, 26..=30, 40..=41, 44..=50]);
+        set.remove(0..=u32::MAX);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn interval_set_contains_test_1() {
+        let set = IntervalSet::from_iter([0..=10, 20..=30]);
+        assert!(set.contains(0));
+        assert!(set.contains(25));
+        assert!(!set.contains(15));
+        assert!(!set.contains(31));
+    }
+
+    #[test]
+    fn interval_set_union_test_1() {
+        let a = IntervalSet::from_iter([0..=10, 20..=30]);
+        let b = IntervalSet::from_iter([5..=15, 31..=40, 50..=60]);
+        assert_eq!(a.union(&b).ranges(), [0..=15, 20..=40, 50..=60]);
+    }
+
+    #[test]
+    fn interval_set_intersection_test_1() {
+        let a = IntervalSet::from_iter([0..=10, 20..=30, 45..=47]);
+        let b = IntervalSet::from_iter([5..=25, 30..=46]);
+        assert_eq!(
+            a.intersection(&b).ranges(),
+            [5..=10, 20..=25, 30..=30, 45..=46]
+        );
+    }
+
+    #[test]
+    fn interval_set_complement_test_1() {
+        let set = IntervalSet::from_iter([5..=10, 20..=u32::MAX]);
+        assert_eq!(set.complement().ranges(), [0..=4, 11..=19]);
+        assert_eq!(set.complement().complement(), set);
+        assert_eq!(IntervalSet::new().complement(), IntervalSet::full());
+        assert!(IntervalSet::full().complement().is_empty());
+    }
+
     #[test]
     fn parse_ranges_test_1() {
         let lines = ["100-145", "79-99", "11-36", "20-25", "52-62", "60-71"];
-        let correct = HashSet::from_iter([11..=36, 52..=71, 79..=145]);
+        let correct = IntervalSet::from_iter([11..=36, 52..=71, 79..=145]);
         assert_eq!(parse_ranges(&lines).unwrap(), correct);
     }
 
+    #[test]
+    fn parse_ranges_test_2() {
+        let lines = ["10.0.0.0/8", "0.0.1.0-0.0.1.255", "192.168.1.1", "5"];
+        let correct = IntervalSet::from_iter([
+            5..=5,
+            256..=511,
+            0x0a000000..=0x0affffff,
+            0xc0a80101..=0xc0a80101,
+        ]);
+        assert_eq!(parse_ranges(&lines).unwrap(), correct);
+        assert_eq!(parse_ranges(&["0.0.0.0/0"]).unwrap(), IntervalSet::full());
+    }
+
+    #[test]
+    fn parse_ranges_test_3() {
+        assert_eq!(
+            parse_ranges(&["1-2", "10.0.0/8"]),
+            Err(ParseRangeError::InvalidAddress {
+                line_number: 1,
+                text: "10.0.0".to_owned()
+            })
+        );
+        assert_eq!(
+            parse_ranges(&["10.0.0.0/33"]),
+            Err(ParseRangeError::InvalidPrefix {
+                line_number: 0,
+                text: "33".to_owned()
+            })
+        );
+        assert_eq!(
+            parse_ranges(&["1-2", "3-4", "9-5"]),
+            Err(ParseRangeError::ReversedRange { line_number: 2 })
+        );
+    }
+
     #[test]
     fn lowest_allowed_ip_test_1() {
-        let ranges = HashSet::from_iter([0..=14, 17..=28, 32..=40]);
+        let ranges = IntervalSet::from_iter([0..=14, 17..=28, 32..=40]);
         assert_eq!(lowest_allowed_ip(&ranges), Some(15));
     }
 
     #[test]
     fn lowest_allowed_ip_test_2() {
-        let ranges = HashSet::from_iter([0..=u32::MAX]);
+        let ranges = IntervalSet::from_iter([0..=u32::MAX]);
         assert_eq!(lowest_allowed_ip(&ranges), None);
     }
 
+    #[test]
+    fn lowest_allowed_ip_test_3() {
+        let ranges = IntervalSet::from_iter([3..=14]);
+        assert_eq!(lowest_allowed_ip(&ranges), Some(0));
+    }
+
     #[test]
     fn count_allowed_ips_test_1() {
-        let ranges = HashSet::from_iter([0..=56, 60..=104, 115..=u32::MAX]);
+        let ranges = IntervalSet::from_iter([0..=56, 60..=104, 115..=u32::MAX]);
         assert_eq!(count_allowed_ips(&ranges), 13);
     }
 
     #[test]
     fn count_allowed_ips_test_2() {
-        let ranges = HashSet::from_iter([0..=u32::MAX]);
+        let ranges = IntervalSet::from_iter([0..=u32::MAX]);
         assert_eq!(count_allowed_ips(&ranges), 0);
     }
 }
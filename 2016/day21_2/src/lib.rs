@@ -1,3 +1,4 @@
+use itertools::Itertools;
 use sscanf::sscanf;
 use std::str::FromStr;
 
@@ -51,6 +52,7 @@ pub enum OperationErr {
     OutOfBounds { index: usize },
     LetterNotFound { letter: char },
     NoInverse,
+    LengthMismatch { expected: usize, found: usize },
 }
 
 fn swap_positions(s: &str, pos_x: usize, pos_y: usize) -> Result<String, OperationErr> {
@@ -181,6 +183,252 @@ pub fn invert_operations(ops: &[Operation]) -> Vec<Operation> {
         .collect()
 }
 
+// output[i] = input[sources[i]]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Permutation {
+    sources: Vec<usize>,
+}
+
+impl Permutation {
+    pub fn identity(len: usize) -> Self {
+        Permutation {
+            sources: (0..len).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    pub fn apply(&self, s: &str) -> Result<String, OperationErr> {
+        let char_vec: Vec<_> = s.chars().collect();
+        if char_vec.len() != self.len() {
+            return Err(OperationErr::LengthMismatch {
+                expected: self.len(),
+                found: char_vec.len(),
+            });
+        }
+        Ok(self.sources.iter().map(|&i| char_vec[i]).collect())
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut sources = vec![0; self.len()];
+        for (i, &source) in self.sources.iter().enumerate() {
+            sources[source] = i;
+        }
+        Permutation { sources }
+    }
+
+    // the permutation equivalent to applying `self` and then `next`
+    pub fn then(&self, next: &Permutation) -> Self {
+        Permutation {
+            sources: next.sources.iter().map(|&i| self.sources[i]).collect(),
+        }
+    }
+
+    fn rotated_right(len: usize, distance: usize) -> Self {
+        let mut sources: Vec<_> = (0..len).collect();
+        if len > 0 {
+            sources.rotate_right(distance % len);
+        }
+        Permutation { sources }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CompileError {
+    OutOfBounds { op: Operation, index: usize },
+    LetterDependent { op: Operation },
+}
+
+fn positional_permutation(op: &Operation, len: usize) -> Result<Permutation, CompileError> {
+    let mut sources: Vec<_> = (0..len).collect();
+    let check = |index: usize| {
+        if index < len {
+            Ok(index)
+        } else {
+            Err(CompileError::OutOfBounds {
+                op: op.clone(),
+                index,
+            })
+        }
+    };
+    match *op {
+        Operation::SwapPositions(x, y) => sources.swap(check(x)?, check(y)?),
+        Operation::RotateLeftFixed(dist) => {
+            return Ok(Permutation::rotated_right(len, len - dist % len.max(1)));
+        }
+        Operation::RotateRightFixed(dist) => return Ok(Permutation::rotated_right(len, dist)),
+        Operation::Reverse(x, y) => {
+            check(x)?;
+            check(y)?;
+            if x <= y {
+                sources[x..=y].reverse();
+            }
+        }
+        Operation::Move(x, y) => {
+            let letter = sources.remove(check(x)?);
+            sources.insert(check(y)?, letter);
+        }
+        Operation::SwapLetters(..)
+        | Operation::RotateBasedOnLetter(_)
+        | Operation::RotateBasedOnLetterInv(_) => {
+            return Err(CompileError::LetterDependent { op: op.clone() });
+        }
+    }
+    Ok(Permutation { sources })
+}
+
+pub fn compile_permutation(ops: &[Operation], len: usize) -> Result<Permutation, CompileError> {
+    ops.iter().try_fold(Permutation::identity(len), |perm, op| {
+        Ok(perm.then(&positional_permutation(op, len)?))
+    })
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Step {
+    Positions(Permutation),
+    SwapLetters(char, char),
+    RotateBasedOnLetter(char),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    len: usize,
+    steps: Vec<Step>,
+}
+
+// rotation to the right applied when the letter is at `index`
+fn letter_rotation(index: usize) -> usize {
+    if index >= 4 { index + 2 } else { index + 1 }
+}
+
+// undoable only if no two indices i land on the same (i + letter_rotation(i)) % len
+pub fn letter_rotation_is_invertible(len: usize) -> bool {
+    let mut seen = vec![false; len];
+    for index in 0..len {
+        let end = (index + letter_rotation(index)) % len;
+        if seen[end] {
+            return false;
+        }
+        seen[end] = true;
+    }
+    true
+}
+
+impl Program {
+    pub fn compile(ops: &[Operation], len: usize) -> Result<Program, CompileError> {
+        let mut steps = vec![];
+        let mut current: Option<Permutation> = None;
+        for op in ops {
+            let step = match *op {
+                Operation::SwapLetters(x, y) => Step::SwapLetters(x, y),
+                Operation::RotateBasedOnLetter(c) => Step::RotateBasedOnLetter(c),
+                Operation::RotateBasedOnLetterInv(_) => {
+                    return Err(CompileError::LetterDependent { op: op.clone() });
+                }
+                _ => {
+                    let perm = positional_permutation(op, len)?;
+                    current = Some(match current {
+                        Some(prev) => prev.then(&perm),
+                        None => perm,
+                    });
+                    continue;
+                }
+            };
+            if let Some(perm) = current.take() {
+                steps.push(Step::Positions(perm));
+            }
+            steps.push(step);
+        }
+        if let Some(perm) = current {
+            steps.push(Step::Positions(perm));
+        }
+        Ok(Program { len, steps })
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn is_invertible(&self) -> bool {
+        letter_rotation_is_invertible(self.len)
+            || !self
+                .steps
+                .iter()
+                .any(|step| matches!(step, Step::RotateBasedOnLetter(_)))
+    }
+
+    pub fn apply(&self, s: &str) -> Result<String, OperationErr> {
+        let mut s = s.to_owned();
+        for step in &self.steps {
+            s = match step {
+                Step::Positions(perm) => perm.apply(&s)?,
+                Step::SwapLetters(x, y) => swap_letters(&s, *x, *y)?,
+                Step::RotateBasedOnLetter(c) => rotate_based_on_letter(&s, *c)?,
+            };
+        }
+        Ok(s)
+    }
+
+    // every string that scrambles into `s`, sorted
+    pub fn preimages(&self, s: &str) -> Result<Vec<String>, OperationErr> {
+        let mut candidates = vec![s.to_owned()];
+        for step in self.steps.iter().rev() {
+            candidates = match step {
+                Step::Positions(perm) => {
+                    let inverse = perm.inverse();
+                    candidates
+                        .iter()
+                        .map(|candidate| inverse.apply(candidate))
+                        .collect::<Result<_, _>>()?
+                }
+                Step::SwapLetters(x, y) => candidates
+                    .iter()
+                    .map(|candidate| swap_letters(candidate, *x, *y))
+                    .collect::<Result<_, _>>()?,
+                Step::RotateBasedOnLetter(c) => {
+                    let mut previous = vec![];
+                    for candidate in &candidates {
+                        previous.extend(letter_rotation_preimages(candidate, *c)?);
+                    }
+                    previous
+                }
+            };
+        }
+        candidates.sort();
+        candidates.dedup();
+        Ok(candidates)
+    }
+}
+
+fn letter_rotation_preimages(s: &str, letter: char) -> Result<Vec<String>, OperationErr> {
+    let len = s.chars().count();
+    let mut preimages = vec![];
+    for distance in 0..len {
+        let candidate = rotate_left(s, distance);
+        if rotate_based_on_letter(&candidate, letter)? == s {
+            preimages.push(candidate);
+        }
+    }
+    Ok(preimages)
+}
+
+pub fn brute_force_preimages(s: &str, ops: &[Operation]) -> Vec<String> {
+    let len = s.chars().count();
+    s.chars()
+        .permutations(len)
+        .map(String::from_iter)
+        .filter(|candidate| apply_operations(candidate, ops).is_ok_and(|result| result == s))
+        .sorted()
+        .dedup()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -431,4 +679,97 @@ mod tests {
         );
         assert_eq!(invert_operations(&inverted), ops);
     }
+
+    fn example_ops() -> Vec<Operation> {
+        use Operation::*;
+        vec![
+            SwapPositions(4, 0),
+            SwapLetters('d', 'b'),
+            Reverse(0, 4),
+            RotateLeftFixed(1),
+            Move(1, 4),
+            Move(3, 0),
+            RotateBasedOnLetter('b'),
+            RotateBasedOnLetter('d'),
+        ]
+    }
+
+    #[test]
+    fn compile_permutation_test_1() {
+        use Operation::*;
+        let ops = [
+            SwapPositions(4, 0),
+            Reverse(0, 4),
+            RotateLeftFixed(1),
+            Move(1, 4),
+            RotateRightFixed(3),
+        ];
+        let perm = compile_permutation(&ops, 6).unwrap();
+        let s = "abcdef";
+        assert_eq!(perm.apply(s).unwrap(), apply_operations(s, &ops).unwrap());
+        let inverted = apply_operations(s, &invert_operations(&ops)).unwrap();
+        assert_eq!(perm.inverse().apply(s).unwrap(), inverted);
+    }
+
+    #[test]
+    fn compile_permutation_test_2() {
+        use Operation::*;
+        let ops = [SwapPositions(1, 0), SwapLetters('a', 'b')];
+        assert_eq!(
+            compile_permutation(&ops, 5),
+            Err(CompileError::LetterDependent {
+                op: SwapLetters('a', 'b')
+            })
+        );
+        assert_eq!(
+            compile_permutation(&[Move(2, 5)], 5),
+            Err(CompileError::OutOfBounds {
+                op: Move(2, 5),
+                index: 5
+            })
+        );
+    }
+
+    #[test]
+    fn letter_rotation_is_invertible_test_1() {
+        let invertible = (1..=16)
+            .filter(|&len| letter_rotation_is_invertible(len))
+            .collect::<Vec<_>>();
+        assert_eq!(invertible, [1, 3, 8]);
+    }
+
+    #[test]
+    fn program_test_1() {
+        let ops = example_ops();
+        let program = Program::compile(&ops, 5).unwrap();
+        assert_eq!(program.steps().len(), 5);
+        assert_eq!(program.apply("abcde"), Ok("decab".to_owned()));
+        assert!(!program.is_invertible());
+        assert!(Program::compile(&ops, 8).unwrap().is_invertible());
+    }
+
+    #[test]
+    fn program_preimages_test_1() {
+        let program = Program::compile(&example_ops(), 8).unwrap();
+        assert_eq!(
+            program.preimages("fbdecgha"),
+            Ok(vec!["abcdefgh".to_owned()])
+        );
+    }
+
+    #[test]
+    fn program_preimages_test_2() {
+        let ops = example_ops();
+        for len in 5..=6 {
+            let program = Program::compile(&ops, len).unwrap();
+            let letters = String::from_iter(('a'..='z').take(len));
+            let scrambled_strings = letters.chars().permutations(len).step_by(7);
+            for scrambled in scrambled_strings.map(String::from_iter) {
+                assert_eq!(
+                    program.preimages(&scrambled).unwrap(),
+                    brute_force_preimages(&scrambled, &ops)
+                );
+            }
+        }
+    }
 }
@@ -1,14 +1,10 @@
+use hash_mining::{Miner, hex_char, nibble};
+
 pub fn compute_code(door_id: &str) -> String {
-    let mut code = String::default();
-    let mut index = 0;
-    while code.len() < 8 {
-        let hash = format!("{:x}", md5::compute(format!("{door_id}{index}")));
-        if &hash[0..5] == "00000" {
-            code.push(hash.chars().nth(5).unwrap());
-        }
-        index += 1;
-    }
-    code
+    Miner::new(door_id, 5)
+        .take(8)
+        .map(|hit| hex_char(nibble(&hit.digest, 5)))
+        .collect()
 }
 
 #[cfg(test)]
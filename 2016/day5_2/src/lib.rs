@@ -1,22 +1,117 @@
-pub fn compute_code(door_id: &str) -> String {
-    let mut index = 0;
-    let mut status = [false; 8];
-    let mut code = ['_'; 8];
-    while status != [true; 8] {
-        let hash = format!("{:x}", md5::compute(format!("{door_id}{index}")));
-        if &hash[0..5] == "00000" {
-            let position = (hash.chars().nth(5).unwrap() as i32 - '0' as i32) as usize;
-            if position <= 7 && !status[position] {
-                status[position] = true;
-                code[position] = hash.chars().nth(6).unwrap();
-                println!("{}", String::from_iter(code));
+use std::io::{self, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hash_mining::{Miner, hex_char, nibble};
+
+const CODE_LENGTH: usize = 8;
+const FRAME_TIME: Duration = Duration::from_millis(40);
+
+type PartialCode = [Option<char>; CODE_LENGTH];
+
+#[derive(Debug)]
+pub struct CodeDigits {
+    hits: Miner,
+    status: [bool; CODE_LENGTH],
+}
+
+impl CodeDigits {
+    pub fn new(door_id: &str) -> Self {
+        CodeDigits {
+            hits: Miner::new(door_id, 5),
+            status: [false; CODE_LENGTH],
+        }
+    }
+}
+
+impl Iterator for CodeDigits {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.status != [true; CODE_LENGTH] {
+            let hit = self.hits.next()?;
+            let position = nibble(&hit.digest, 5) as usize;
+            if position < CODE_LENGTH && !self.status[position] {
+                self.status[position] = true;
+                return Some((position, hex_char(nibble(&hit.digest, 6))));
             }
         }
-        index += 1;
+        None
+    }
+}
+
+pub fn compute_code(door_id: &str) -> String {
+    let mut code = ['_'; CODE_LENGTH];
+    for (position, c) in CodeDigits::new(door_id) {
+        code[position] = c;
     }
     String::from_iter(code)
 }
 
+pub fn render(code: &PartialCode, mut filler: impl FnMut() -> char) -> String {
+    code.iter().map(|c| c.unwrap_or_else(&mut filler)).collect()
+}
+
+// prints the code with '_' for unknown digits each time a digit is found
+pub fn print_progress<W: Write>(
+    digits: impl Iterator<Item = (usize, char)>,
+    out: &mut W,
+) -> io::Result<String> {
+    let mut code = [None; CODE_LENGTH];
+    for (position, c) in digits {
+        code[position] = Some(c);
+        writeln!(out, "{}", render(&code, || '_'))?;
+    }
+    Ok(render(&code, || '_'))
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn from_clock() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos());
+        XorShift(u64::from(nanos) | 1)
+    }
+
+    fn next_hex(&mut self) -> char {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        hex_char((self.0 & 0xf) as u8)
+    }
+}
+
+// mines in the background while redrawing one line, unknown digits cycling randomly
+pub fn animate<W: Write>(door_id: &str, out: &mut W) -> io::Result<String> {
+    let (sender, receiver) = mpsc::channel();
+    let mut digits = CodeDigits::new(door_id);
+    let miner = thread::spawn(move || {
+        for digit in digits.by_ref() {
+            if sender.send(digit).is_err() {
+                break;
+            }
+        }
+    });
+    let mut rng = XorShift::from_clock();
+    let mut code = [None; CODE_LENGTH];
+    loop {
+        write!(out, "\r{}", render(&code, || rng.next_hex()))?;
+        out.flush()?;
+        match receiver.recv_timeout(FRAME_TIME) {
+            Ok((position, c)) => code[position] = Some(c),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    miner.join().expect("mining thread panicked");
+    let code = render(&code, || '_');
+    writeln!(out, "\r{code}")?;
+    Ok(code)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -25,4 +120,22 @@ mod tests {
     fn compute_code_test_1() {
         assert_eq!(compute_code("abc"), "05ace8e3");
     }
+
+    #[test]
+    fn render_test_1() {
+        let code = [None, Some('5'), None, None, Some('e'), None, None, None];
+        assert_eq!(render(&code, || '*'), "*5**e***");
+    }
+
+    #[test]
+    fn print_progress_test_1() {
+        let mut out = vec![];
+        let digits = [(1, '5'), (4, 'e'), (0, '0')];
+        let code = print_progress(digits.into_iter(), &mut out).unwrap();
+        assert_eq!(code, "05__e___");
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "_5______\n_5__e___\n05__e___\n"
+        );
+    }
 }
@@ -1,10 +1,15 @@
-use std::fs::read_to_string;
+use std::{env, fs::read_to_string, io};
 
-use day5_2::compute_code;
+use day5_2::{CodeDigits, animate, print_progress};
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
     let input = input.trim().lines().collect::<Vec<_>>();
-    let code = compute_code(input[0]);
+    let mut stdout = io::stdout();
+    let code = if env::args().any(|arg| arg == "--animate") {
+        animate(input[0], &mut stdout).unwrap()
+    } else {
+        print_progress(CodeDigits::new(input[0]), &mut stdout).unwrap()
+    };
     println!("The door code is: {code}");
 }
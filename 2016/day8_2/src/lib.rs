@@ -1,48 +1,181 @@
 use sscanf::sscanf;
+use std::io::{self, Write};
 use std::str::FromStr;
+use thiserror::Error;
 
-#[derive(Debug, PartialEq)]
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 6;
+
+// letters of the Advent of Code display font, each drawn in a 5x6 cell
+// including the blank column that separates it from the next one
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##..", "#..#.", "#..#.", "####.", "#..#.", "#..#."]),
+    ('B', ["###..", "#..#.", "###..", "#..#.", "#..#.", "###.."]),
+    ('C', [".##..", "#..#.", "#....", "#....", "#..#.", ".##.."]),
+    ('E', ["####.", "#....", "###..", "#....", "#....", "####."]),
+    ('F', ["####.", "#....", "###..", "#....", "#....", "#...."]),
+    ('G', [".##..", "#..#.", "#....", "#.##.", "#..#.", ".###."]),
+    ('H', ["#..#.", "#..#.", "####.", "#..#.", "#..#.", "#..#."]),
+    ('J', ["..##.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', ["#..#.", "#.#..", "##...", "#.#..", "#.#..", "#..#."]),
+    ('L', ["#....", "#....", "#....", "#....", "#....", "####."]),
+    ('O', [".##..", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('P', ["###..", "#..#.", "#..#.", "###..", "#....", "#...."]),
+    ('R', ["###..", "#..#.", "#..#.", "###..", "#.#..", "#..#."]),
+    ('S', [".###.", "#....", "#....", ".##..", "...#.", "###.."]),
+    ('U', ["#..#.", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####.", "...#.", "..#..", ".#...", "#....", "####."]),
+];
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("failed to write image")]
+    Io(#[from] io::Error),
+    #[error("failed to encode PNG")]
+    Png(#[from] png::EncodingError),
+    #[error("failed to encode GIF")]
+    Gif(#[from] gif::EncodingError),
+    #[error("image is too large at scale {scale}")]
+    TooLarge { scale: usize },
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum InstructionError {
+    #[error("rect {a}x{b} does not fit on a {width}x{height} screen")]
+    RectTooLarge {
+        a: usize,
+        b: usize,
+        width: usize,
+        height: usize,
+    },
+    #[error("row y={y} is outside a screen of height {height}")]
+    RowOutOfBounds { y: usize, height: usize },
+    #[error("column x={x} is outside a screen of width {width}")]
+    ColumnOutOfBounds { x: usize, width: usize },
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("line #{line_number} is not a valid instruction: {line}")]
+pub struct ParseInstructionsError {
+    line_number: usize,
+    line: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Screen {
-    pixels: [[bool; 50]; 6],
+    width: usize,
+    pixels: Vec<Vec<bool>>,
 }
 
 impl Default for Screen {
     fn default() -> Self {
-        Self {
-            pixels: [[false; 50]; 6],
-        }
+        Self::new(50, 6)
     }
 }
 
 impl Screen {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            pixels: vec![vec![false; width]; height],
+        }
+    }
+
     fn rect(&mut self, a: usize, b: usize) {
-        for x in 0..a {
-            for y in 0..b {
-                self.pixels[y][x] = true;
-            }
+        for row in &mut self.pixels[..b] {
+            row[..a].fill(true);
         }
     }
 
     fn rotate_row(&mut self, y: usize, len: usize) {
-        let old_row = self.pixels[y];
-        for (x, old_pixel) in old_row.into_iter().enumerate() {
-            self.pixels[y][(x + len) % 50] = old_pixel;
+        if self.width > 0 {
+            self.pixels[y].rotate_right(len % self.width);
         }
     }
 
     fn rotate_col(&mut self, x: usize, len: usize) {
+        let height = self.height();
         let old_col = self.pixels.iter().map(|row| row[x]).collect::<Vec<bool>>();
         for (y, old_pixel) in old_col.into_iter().enumerate() {
-            self.pixels[(y + len) % 6][x] = old_pixel;
+            self.pixels[(y + len) % height][x] = old_pixel;
+        }
+    }
+
+    // like rotate_row, but pixels pushed past the edge are lost and the
+    // vacated ones are turned off
+    fn shift_row(&mut self, y: usize, len: usize) {
+        let row = &mut self.pixels[y];
+        let len = len.min(row.len());
+        row.rotate_right(len);
+        row[..len].fill(false);
+    }
+
+    fn shift_col(&mut self, x: usize, len: usize) {
+        for y in (0..self.height()).rev() {
+            self.pixels[y][x] = y >= len && self.pixels[y - len][x];
         }
     }
 
-    pub fn apply_instruction(&mut self, inst: &Instruction) {
+    fn check_row(&self, y: usize) -> Result<(), InstructionError> {
+        if y < self.height() {
+            Ok(())
+        } else {
+            Err(InstructionError::RowOutOfBounds {
+                y,
+                height: self.height(),
+            })
+        }
+    }
+
+    fn check_col(&self, x: usize) -> Result<(), InstructionError> {
+        if x < self.width {
+            Ok(())
+        } else {
+            Err(InstructionError::ColumnOutOfBounds {
+                x,
+                width: self.width,
+            })
+        }
+    }
+
+    pub fn apply_instruction(&mut self, inst: &Instruction) -> Result<(), InstructionError> {
         match *inst {
-            Instruction::Rect(a, b) => self.rect(a, b),
-            Instruction::RotateRow(a, b) => self.rotate_row(a, b),
-            Instruction::RotateCol(a, b) => self.rotate_col(a, b),
+            Instruction::Rect(a, b) => {
+                if a > self.width || b > self.height() {
+                    return Err(InstructionError::RectTooLarge {
+                        a,
+                        b,
+                        width: self.width,
+                        height: self.height(),
+                    });
+                }
+                self.rect(a, b);
+            }
+            Instruction::RotateRow(a, b) => {
+                self.check_row(a)?;
+                self.rotate_row(a, b);
+            }
+            Instruction::RotateCol(a, b) => {
+                self.check_col(a)?;
+                self.rotate_col(a, b);
+            }
+            Instruction::ShiftRow(a, b) => {
+                self.check_row(a)?;
+                self.shift_row(a, b);
+            }
+            Instruction::ShiftCol(a, b) => {
+                self.check_col(a)?;
+                self.shift_col(a, b);
+            }
+            Instruction::Clear => self.pixels.iter_mut().for_each(|row| row.fill(false)),
+            Instruction::Invert => self
+                .pixels
+                .iter_mut()
+                .flatten()
+                .for_each(|pixel| *pixel = !*pixel),
         };
+        Ok(())
     }
 
     pub fn total_on(&self) -> u64 {
@@ -50,11 +183,102 @@ impl Screen {
     }
 
     pub fn print(&self) {
-        for row in self.pixels {
+        for row in &self.pixels {
             let line = String::from_iter(row.iter().map(|&pixel| if pixel { 'o' } else { ' ' }));
             println!("{line}");
         }
     }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.pixels.len()
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y][x]
+    }
+
+    pub fn apply_all_recording(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> Result<Vec<Screen>, InstructionError> {
+        let mut frames = vec![self.clone()];
+        for inst in instructions {
+            self.apply_instruction(inst)?;
+            frames.push(self.clone());
+        }
+        Ok(frames)
+    }
+
+    pub fn write_pbm<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "P1")?;
+        writeln!(out, "{} {}", self.width(), self.height())?;
+        for y in 0..self.height() {
+            let row = (0..self.width())
+                .map(|x| if self.pixel(x, y) { "1" } else { "0" })
+                .collect::<Vec<_>>();
+            writeln!(out, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+
+    // grayscale image with every screen pixel drawn as a `scale` x `scale`
+    // square, lit pixels being white
+    fn scaled_image(&self, scale: usize) -> Vec<u8> {
+        let mut image = Vec::with_capacity(self.width() * self.height() * scale * scale);
+        for y in 0..self.height() * scale {
+            for x in 0..self.width() * scale {
+                image.push(if self.pixel(x / scale, y / scale) {
+                    255
+                } else {
+                    0
+                });
+            }
+        }
+        image
+    }
+
+    // image dimensions at `scale`, if they fit the format's integer type
+    fn scaled_size<T: TryFrom<usize>>(&self, scale: usize) -> Result<(T, T), ExportError> {
+        let scaled = |len: usize| len.checked_mul(scale).and_then(|len| T::try_from(len).ok());
+        match (scaled(self.width()), scaled(self.height())) {
+            (Some(width), Some(height)) => Ok((width, height)),
+            _ => Err(ExportError::TooLarge { scale }),
+        }
+    }
+
+    pub fn write_png<W: Write>(&self, out: W, scale: usize) -> Result<(), ExportError> {
+        let (width, height) = self.scaled_size::<u32>(scale)?;
+        let mut encoder = png::Encoder::new(out, width, height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.scaled_image(scale))?;
+        Ok(())
+    }
+
+    // the displayed text, with '?' for any cell that is not a known letter
+    pub fn ocr(&self) -> String {
+        (0..self.width().div_ceil(GLYPH_WIDTH))
+            .map(|cell| {
+                let x0 = cell * GLYPH_WIDTH;
+                let matches = |glyph: &[&str; GLYPH_HEIGHT]| {
+                    glyph.iter().enumerate().all(|(y, line)| {
+                        line.chars().enumerate().all(|(dx, c)| {
+                            let lit = x0 + dx < self.width() && self.pixel(x0 + dx, y);
+                            lit == (c == '#')
+                        })
+                    })
+                };
+                FONT.iter()
+                    .find(|(_, glyph)| matches(glyph))
+                    .map_or('?', |&(letter, _)| letter)
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -62,6 +286,10 @@ pub enum Instruction {
     Rect(usize, usize),
     RotateRow(usize, usize),
     RotateCol(usize, usize),
+    ShiftRow(usize, usize),
+    ShiftCol(usize, usize),
+    Clear,
+    Invert,
 }
 
 impl FromStr for Instruction {
@@ -77,10 +305,60 @@ impl FromStr for Instruction {
         if let Ok((a, b)) = sscanf!(s, "rotate column x={usize} by {usize}") {
             return Ok(Instruction::RotateCol(a, b));
         }
-        Err(sscanf::Error::MatchFailed)
+        if let Ok((a, b)) = sscanf!(s, "shift row y={usize} by {usize}") {
+            return Ok(Instruction::ShiftRow(a, b));
+        }
+        if let Ok((a, b)) = sscanf!(s, "shift column x={usize} by {usize}") {
+            return Ok(Instruction::ShiftCol(a, b));
+        }
+        match s {
+            "clear" => Ok(Instruction::Clear),
+            "invert" => Ok(Instruction::Invert),
+            _ => Err(sscanf::Error::MatchFailed),
+        }
     }
 }
 
+pub fn parse_instructions(input: &str) -> Result<Vec<Instruction>, ParseInstructionsError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            line.trim().parse().map_err(|_| ParseInstructionsError {
+                line_number,
+                line: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn write_gif<W: Write>(
+    frames: &[Screen],
+    out: W,
+    scale: usize,
+    delay: u16,
+) -> Result<(), ExportError> {
+    let Some(first) = frames.first() else {
+        return Ok(());
+    };
+    let (width, height) = first.scaled_size::<u16>(scale)?;
+    let palette = [0, 0, 0, 255, 255, 255];
+    let mut encoder = gif::Encoder::new(out, width, height, &palette)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for screen in frames {
+        let indices = screen
+            .scaled_image(scale)
+            .into_iter()
+            .map(|value| (value > 0) as u8)
+            .collect::<Vec<_>>();
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, indices, None);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,7 +399,7 @@ mod tests {
     #[test]
     fn screen_rect_test_1() {
         let mut screen = Screen::default();
-        screen.apply_instruction(&Instruction::Rect(8, 2));
+        screen.apply_instruction(&Instruction::Rect(8, 2)).unwrap();
         for x in 0..50 {
             for y in 0..6 {
                 if x < 8 && y < 2 {
@@ -136,8 +414,10 @@ mod tests {
     #[test]
     fn screen_rotate_row_test_1() {
         let mut screen = Screen::default();
-        screen.apply_instruction(&Instruction::Rect(8, 2));
-        screen.apply_instruction(&Instruction::RotateRow(0, 3));
+        screen.apply_instruction(&Instruction::Rect(8, 2)).unwrap();
+        screen
+            .apply_instruction(&Instruction::RotateRow(0, 3))
+            .unwrap();
         for x in 0..50 {
             for y in 0..6 {
                 if (y == 0 && x >= 3 && x < 11) || (y == 1 && x < 8) {
@@ -152,8 +432,10 @@ mod tests {
     #[test]
     fn screen_rotate_col_test_1() {
         let mut screen = Screen::default();
-        screen.apply_instruction(&Instruction::Rect(8, 5));
-        screen.apply_instruction(&Instruction::RotateCol(3, 3));
+        screen.apply_instruction(&Instruction::Rect(8, 5)).unwrap();
+        screen
+            .apply_instruction(&Instruction::RotateCol(3, 3))
+            .unwrap();
         for x in 0..50 {
             for y in 0..6 {
                 if (x < 8 && x != 3 && y != 5) || (x == 3 && y != 2) {
@@ -168,7 +450,222 @@ mod tests {
     #[test]
     fn screen_total_on_test_1() {
         let mut screen = Screen::default();
-        screen.apply_instruction(&Instruction::Rect(17, 3));
+        screen.apply_instruction(&Instruction::Rect(17, 3)).unwrap();
         assert_eq!(screen.total_on(), 51);
     }
+
+    fn draw_text(text: &str) -> Screen {
+        let mut screen = Screen::default();
+        for (cell, letter) in text.chars().enumerate() {
+            let (_, glyph) = FONT.iter().find(|(c, _)| *c == letter).unwrap();
+            for (y, line) in glyph.iter().enumerate() {
+                for (dx, c) in line.chars().enumerate() {
+                    screen.pixels[y][cell * GLYPH_WIDTH + dx] = c == '#';
+                }
+            }
+        }
+        screen
+    }
+
+    #[test]
+    fn screen_ocr_test_1() {
+        assert_eq!(draw_text("ZJHRKCPLYJ").ocr(), "ZJHRKCPLYJ");
+        assert_eq!(draw_text("ABCEFGHJKL").ocr(), "ABCEFGHJKL");
+        assert_eq!(draw_text("OPRSU").ocr(), "OPRSU?????");
+    }
+
+    #[test]
+    fn screen_ocr_test_2() {
+        let mut screen = draw_text("EF");
+        screen.apply_instruction(&Instruction::Rect(5, 1)).unwrap();
+        assert_eq!(screen.ocr(), "?F????????");
+    }
+
+    #[test]
+    fn screen_ocr_test_3() {
+        // a real puzzle answer, as displayed after running its instructions
+        let display = [
+            "####..##...##..###...##..###..#..#.#...#.##...##..",
+            "#....#..#.#..#.#..#.#..#.#..#.#..#.#...##..#.#..#.",
+            "###..#..#.#..#.#..#.#....#..#.####..#.#.#..#.#..#.",
+            "#....#..#.####.###..#.##.###..#..#...#..####.#..#.",
+            "#....#..#.#..#.#.#..#..#.#....#..#...#..#..#.#..#.",
+            "####..##..#..#.#..#..###.#....#..#...#..#..#..##..",
+        ];
+        let mut screen = Screen::default();
+        for (y, line) in display.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                screen.pixels[y][x] = c == '#';
+            }
+        }
+        assert_eq!(screen.ocr(), "EOARGPHYAO");
+    }
+
+    #[test]
+    fn screen_write_pbm_test_1() {
+        let mut screen = Screen::default();
+        screen.apply_instruction(&Instruction::Rect(2, 1)).unwrap();
+        let mut out = vec![];
+        screen.write_pbm(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "P1");
+        assert_eq!(lines[1], "50 6");
+        assert!(lines[2].starts_with("1 1 0 0"));
+        assert_eq!(lines[7], vec!["0"; 50].join(" "));
+    }
+
+    #[test]
+    fn screen_write_png_test_1() {
+        let screen = draw_text("HO");
+        let mut out = vec![];
+        screen.write_png(&mut out, 2).unwrap();
+        let decoder = png::Decoder::new(out.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!((info.width, info.height), (100, 12));
+        assert_eq!(&buf[..info.buffer_size()], screen.scaled_image(2));
+    }
+
+    #[test]
+    fn write_gif_test_1() {
+        let mut screen = Screen::default();
+        let instructions = [
+            Instruction::Rect(3, 2),
+            Instruction::RotateCol(1, 1),
+            Instruction::RotateRow(0, 4),
+        ];
+        let frames = screen.apply_all_recording(&instructions).unwrap();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0], Screen::default());
+        assert_eq!(frames[3], screen);
+        let mut out = vec![];
+        write_gif(&frames, &mut out, 1, 10).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(out.as_slice()).unwrap();
+        let mut count = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn write_gif_test_2() {
+        let frames = [Screen::new(2000, 1)];
+        // 2000 * 33 no longer fits in the 16 bits of a GIF dimension
+        assert!(write_gif(&frames, &mut vec![], 32, 10).is_ok());
+        assert!(matches!(
+            write_gif(&frames, &mut vec![], 33, 10),
+            Err(ExportError::TooLarge { scale: 33 })
+        ));
+    }
+
+    #[test]
+    fn instruction_from_str_test_5() {
+        assert_eq!("clear".parse::<Instruction>().unwrap(), Instruction::Clear);
+        assert_eq!(
+            "invert".parse::<Instruction>().unwrap(),
+            Instruction::Invert
+        );
+        assert_eq!(
+            "shift row y=1 by 4".parse::<Instruction>().unwrap(),
+            Instruction::ShiftRow(1, 4)
+        );
+        assert_eq!(
+            "shift column x=3 by 2".parse::<Instruction>().unwrap(),
+            Instruction::ShiftCol(3, 2)
+        );
+    }
+
+    #[test]
+    fn parse_instructions_test_1() {
+        let input = "rect 3x2\n\nrotate column x=1 by 1\nrotate colum x=1 by 1\n";
+        assert_eq!(
+            parse_instructions(input).unwrap_err(),
+            ParseInstructionsError {
+                line_number: 3,
+                line: "rotate colum x=1 by 1".to_string()
+            }
+        );
+        assert_eq!(parse_instructions(&input[..32]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn screen_new_test_1() {
+        // example from the puzzle description
+        let mut screen = Screen::new(7, 3);
+        let instructions = parse_instructions(
+            "rect 3x2\nrotate column x=1 by 1\nrotate row y=0 by 4\nrotate column x=1 by 1",
+        )
+        .unwrap();
+        for inst in &instructions {
+            screen.apply_instruction(inst).unwrap();
+        }
+        let rows = screen
+            .pixels
+            .iter()
+            .map(|row| String::from_iter(row.iter().map(|&p| if p { '#' } else { '.' })))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [".#..#.#", "#.#....", ".#....."]);
+        assert_eq!(screen.total_on(), 6);
+    }
+
+    #[test]
+    fn screen_apply_instruction_test_1() {
+        let mut screen = Screen::new(7, 3);
+        assert_eq!(
+            screen.apply_instruction(&Instruction::Rect(8, 1)),
+            Err(InstructionError::RectTooLarge {
+                a: 8,
+                b: 1,
+                width: 7,
+                height: 3
+            })
+        );
+        assert_eq!(
+            screen.apply_instruction(&Instruction::RotateRow(3, 1)),
+            Err(InstructionError::RowOutOfBounds { y: 3, height: 3 })
+        );
+        assert_eq!(
+            screen.apply_instruction(&Instruction::ShiftCol(7, 1)),
+            Err(InstructionError::ColumnOutOfBounds { x: 7, width: 7 })
+        );
+        assert_eq!(screen, Screen::new(7, 3));
+    }
+
+    #[test]
+    fn screen_shift_test_1() {
+        let mut screen = Screen::new(4, 3);
+        screen.apply_instruction(&Instruction::Rect(2, 2)).unwrap();
+        screen
+            .apply_instruction(&Instruction::ShiftRow(0, 3))
+            .unwrap();
+        screen
+            .apply_instruction(&Instruction::ShiftCol(0, 2))
+            .unwrap();
+        assert_eq!(
+            screen.pixels,
+            [
+                [false, false, false, true],
+                [false, true, false, false],
+                [false, false, false, false]
+            ]
+        );
+        screen
+            .apply_instruction(&Instruction::ShiftRow(1, 9))
+            .unwrap();
+        assert_eq!(screen.total_on(), 1);
+    }
+
+    #[test]
+    fn screen_invert_clear_test_1() {
+        let mut screen = Screen::default();
+        screen.apply_instruction(&Instruction::Rect(10, 3)).unwrap();
+        screen.apply_instruction(&Instruction::Invert).unwrap();
+        assert_eq!(screen.total_on(), 270);
+        screen.apply_instruction(&Instruction::Clear).unwrap();
+        assert_eq!(screen, Screen::default());
+    }
 }
@@ -1,16 +1,26 @@
-use std::fs::read_to_string;
+use std::{env, fs::File, fs::read_to_string};
 
-use day8_2::{Instruction, Screen};
+use day8_2::{Screen, parse_instructions, write_gif};
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
-    let input = input.trim().lines().collect::<Vec<_>>();
     let mut screen = Screen::default();
-    let instructions = input
-        .iter()
-        .map(|line| line.parse::<Instruction>().unwrap());
-    for inst in instructions {
-        screen.apply_instruction(&inst);
-    }
+    let instructions = parse_instructions(&input).unwrap();
+    let frames = screen.apply_all_recording(&instructions).unwrap();
     screen.print();
+    println!("The screen displays: {}", screen.ocr());
+
+    let args = env::args().collect::<Vec<_>>();
+    for pair in args.windows(2) {
+        match pair[0].as_str() {
+            "--pbm" => screen
+                .write_pbm(&mut File::create(&pair[1]).unwrap())
+                .unwrap(),
+            "--png" => screen
+                .write_png(File::create(&pair[1]).unwrap(), 8)
+                .unwrap(),
+            "--gif" => write_gif(&frames, File::create(&pair[1]).unwrap(), 8, 5).unwrap(),
+            _ => {}
+        }
+    }
 }
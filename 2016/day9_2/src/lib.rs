@@ -1,5 +1,8 @@
+use std::io::{self, Read};
+
 use regex::Regex;
 use sscanf::sscanf;
+use thiserror::Error;
 
 pub fn decompress(compressed: &str) -> String {
     let mut decomp = String::new();
@@ -23,24 +26,236 @@ pub fn decompress(compressed: &str) -> String {
     decomp
 }
 
-pub fn decompressed_size(compressed: &str) -> usize {
+// whitespace is skipped everywhere, even inside markers and marker lengths
+fn is_ignored(byte: u8) -> bool {
+    byte.is_ascii_whitespace()
+}
+
+// byte position after `count` significant bytes starting at `pos`
+fn advance(input: &[u8], mut pos: usize, end: usize, mut count: usize) -> usize {
+    while pos < end && count > 0 {
+        if !is_ignored(input[pos]) {
+            count -= 1;
+        }
+        pos += 1;
+    }
+    pos
+}
+
+#[derive(Debug, PartialEq)]
+struct Marker {
+    len: usize,
+    reps: usize,
+    data_start: usize,
+}
+
+fn parse_number(input: &[u8], pos: &mut usize, end: usize, terminator: u8) -> Option<usize> {
+    let mut number: Option<usize> = None;
+    while *pos < end {
+        let byte = input[*pos];
+        *pos += 1;
+        match byte {
+            b'0'..=b'9' => {
+                let digit = (byte - b'0') as usize;
+                number = Some(number.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+            }
+            _ if byte == terminator => return number,
+            _ if is_ignored(byte) => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn parse_marker(input: &[u8], pos: usize, end: usize) -> Option<Marker> {
+    if input.get(pos) != Some(&b'(') {
+        return None;
+    }
+    let mut cursor = pos + 1;
+    let len = parse_number(input, &mut cursor, end, b'x')?;
+    let reps = parse_number(input, &mut cursor, end, b')')?;
+    Some(Marker {
+        len,
+        reps,
+        data_start: cursor,
+    })
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum DecompressError {
+    #[error("decompressed size overflows")]
+    Overflow,
+}
+
+fn region_size(input: &[u8], start: usize, end: usize) -> Result<usize, DecompressError> {
     let mut total: usize = 0;
-    let reg = Regex::new(r"\(\d+x\d+\)").unwrap();
-    let matches = reg.find_iter(compressed);
-    let mut current_pos = 0;
-    for m in matches {
-        let start = m.range().start;
-        let end = m.range().end;
-        if end <= current_pos {
-            continue;
+    let mut pos = start;
+    while pos < end {
+        let size = if is_ignored(input[pos]) {
+            pos += 1;
+            0
+        } else if let Some(marker) = parse_marker(input, pos, end) {
+            let data_end = advance(input, marker.data_start, end, marker.len);
+            let data_size = region_size(input, marker.data_start, data_end)?;
+            pos = data_end;
+            marker
+                .reps
+                .checked_mul(data_size)
+                .ok_or(DecompressError::Overflow)?
+        } else {
+            pos += 1;
+            1
+        };
+        total = total.checked_add(size).ok_or(DecompressError::Overflow)?;
+    }
+    Ok(total)
+}
+
+pub fn decompressed_size(compressed: &str) -> Result<usize, DecompressError> {
+    region_size(compressed.as_bytes(), 0, compressed.len())
+}
+
+#[derive(Debug)]
+struct Frame {
+    start: usize,
+    end: usize,
+    pos: usize,
+    reps_left: usize,
+}
+
+// expands lazily with one frame per nested marker being repeated
+#[derive(Debug)]
+pub struct Decompressor<'a> {
+    input: &'a [u8],
+    frames: Vec<Frame>,
+}
+
+impl<'a> Decompressor<'a> {
+    pub fn new(compressed: &'a str) -> Self {
+        let input = compressed.as_bytes();
+        Decompressor {
+            input,
+            frames: vec![Frame {
+                start: 0,
+                end: input.len(),
+                pos: 0,
+                reps_left: 1,
+            }],
+        }
+    }
+
+    fn next_byte(&mut self) -> Option<u8> {
+        loop {
+            let frame = self.frames.last_mut()?;
+            if frame.pos >= frame.end {
+                frame.reps_left -= 1;
+                if frame.reps_left == 0 {
+                    self.frames.pop();
+                } else {
+                    frame.pos = frame.start;
+                }
+                continue;
+            }
+            let (pos, end) = (frame.pos, frame.end);
+            let byte = self.input[pos];
+            if is_ignored(byte) {
+                frame.pos += 1;
+            } else if let Some(marker) = parse_marker(self.input, pos, end) {
+                let data_end = advance(self.input, marker.data_start, end, marker.len);
+                frame.pos = data_end;
+                if marker.reps > 0 && data_end > marker.data_start {
+                    self.frames.push(Frame {
+                        start: marker.data_start,
+                        end: data_end,
+                        pos: marker.data_start,
+                        reps_left: marker.reps,
+                    });
+                }
+            } else {
+                frame.pos += 1;
+                return Some(byte);
+            }
         }
-        let (len, reps) = sscanf!(m.as_str(), "({usize}x{usize})").unwrap();
-        total += start - current_pos;
-        let base_len = decompressed_size(&compressed[end..end + len]);
-        total += base_len * reps;
-        current_pos = end + len;
     }
-    total + compressed.len() - current_pos
+}
+
+impl Read for Decompressor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            match self.next_byte() {
+                Some(byte) => {
+                    buf[written] = byte;
+                    written += 1;
+                }
+                None => break,
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum CompressError {
+    #[error("whitespace at position {position} would be dropped by decompression")]
+    Whitespace { position: usize },
+    #[error("non-ASCII character at position {position}")]
+    NonAscii { position: usize },
+}
+
+const MAX_UNIT_LEN: usize = 64;
+
+// a run of R units of length L is worth a "(LxR)" marker only if it saves bytes
+fn best_run(text: &[u8]) -> Option<(usize, usize)> {
+    let mut best = None;
+    let mut best_saving = 0;
+    for unit_len in 1..=MAX_UNIT_LEN.min(text.len() / 2) {
+        let unit = &text[..unit_len];
+        let reps = text
+            .chunks_exact(unit_len)
+            .take_while(|chunk| *chunk == unit)
+            .count();
+        let marker_len = format!("({unit_len}x{reps})").len();
+        let saving = (unit_len * reps).saturating_sub(unit_len + marker_len);
+        if reps >= 2 && saving > best_saving {
+            best = Some((unit_len, reps));
+            best_saving = saving;
+        }
+    }
+    best
+}
+
+fn compress_bytes(text: &[u8], compressed: &mut String) {
+    let mut pos = 0;
+    while pos < text.len() {
+        if let Some((unit_len, reps)) = best_run(&text[pos..]) {
+            let mut unit = String::new();
+            compress_bytes(&text[pos..pos + unit_len], &mut unit);
+            compressed.push_str(&format!("({}x{reps}){unit}", unit.len()));
+            pos += unit_len * reps;
+        } else {
+            if text[pos] == b'(' {
+                // a literal parenthesis is wrapped so it can never start a marker
+                compressed.push_str("(1x1)");
+            }
+            compressed.push(text[pos] as char);
+            pos += 1;
+        }
+    }
+}
+
+pub fn compress(text: &str) -> Result<String, CompressError> {
+    for (position, byte) in text.bytes().enumerate() {
+        if is_ignored(byte) {
+            return Err(CompressError::Whitespace { position });
+        }
+        if !byte.is_ascii() {
+            return Err(CompressError::NonAscii { position });
+        }
+    }
+    let mut compressed = String::new();
+    compress_bytes(text.as_bytes(), &mut compressed);
+    Ok(compressed)
 }
 
 #[cfg(test)]
@@ -67,16 +282,99 @@ mod tests {
 
     #[test]
     fn decompressed_size_test_1() {
-        assert_eq!(decompressed_size("ABRACADABRA"), 11);
+        assert_eq!(decompressed_size("ABRACADABRA"), Ok(11));
     }
 
     #[test]
     fn decompressed_size_test_2() {
-        assert_eq!(decompressed_size("ABRA(3x2)CADABRA"), 14);
+        assert_eq!(decompressed_size("ABRA(3x2)CADABRA"), Ok(14));
     }
 
     #[test]
     fn decompressed_size_test_3() {
-        assert_eq!(decompressed_size("(6x2)(1x3)ABRA(3x2)CADABRA"), 19);
+        assert_eq!(decompressed_size("(6x2)(1x3)ABRA(3x2)CADABRA"), Ok(19));
+    }
+
+    #[test]
+    fn decompressed_size_test_4() {
+        assert_eq!(
+            decompressed_size("(25x3)(3x3)ABC(2x3)XY(5x2)PQRSTX(18x9)(3x2)TWO(5x7)SEVEN"),
+            Ok(445)
+        );
+        assert_eq!(
+            decompressed_size("(27x12)(20x12)(13x14)(7x10)(1x12)A"),
+            Ok(241920)
+        );
+    }
+
+    #[test]
+    fn decompressed_size_test_5() {
+        assert_eq!(decompressed_size("(3x\n2)A BC (1\t0x2\n)X"), Ok(8));
+        assert_eq!(decompressed_size("A(2x2"), Ok(5));
+    }
+
+    #[test]
+    fn decompressed_size_test_6() {
+        let nested = "(99999x99999)".repeat(5) + "A";
+        assert_eq!(decompressed_size(&nested), Err(DecompressError::Overflow));
+        let wide = format!("({}x2)A", usize::MAX / 2 + 1);
+        assert_eq!(decompressed_size(&wide), Ok(2));
+    }
+
+    fn read_all(compressed: &str) -> String {
+        let mut decompressed = String::new();
+        Decompressor::new(compressed)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        decompressed
+    }
+
+    #[test]
+    fn decompressor_test_1() {
+        assert_eq!(read_all("X(8x2)(3x3)ABCY"), "XABCABCABCABCABCABCY");
+        assert_eq!(read_all("(3x\n2)A BC (1\t0x2\n)X"), "ABCABCXX");
+        assert_eq!(read_all("A(0x5)B(2x0)CD"), "AB");
+    }
+
+    #[test]
+    fn decompressor_test_2() {
+        let compressed = "(27x12)(20x12)(13x14)(7x10)(1x12)A";
+        let mut decompressor = Decompressor::new(compressed);
+        let mut buf = [0; 7];
+        let mut total = 0;
+        loop {
+            let read = decompressor.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            assert!(buf[..read].iter().all(|&c| c == b'A'));
+            total += read;
+        }
+        assert_eq!(Ok(total), decompressed_size(compressed));
+    }
+
+    #[test]
+    fn compress_test_1() {
+        for text in [
+            "ABRACADABRA",
+            "ABABABABABABABABABABXYZXYZXYZXYZ",
+            "(1x3)A(2x2)BC)(((",
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+            "",
+        ] {
+            let compressed = compress(text).unwrap();
+            assert_eq!(read_all(&compressed), text);
+            assert_eq!(decompressed_size(&compressed), Ok(text.len()));
+        }
+    }
+
+    #[test]
+    fn compress_test_2() {
+        let text = "XYZ".repeat(40);
+        assert!(compress(&text).unwrap().len() < 12);
+        assert_eq!(
+            compress("AB CD"),
+            Err(CompressError::Whitespace { position: 2 })
+        );
     }
 }
@@ -1,12 +1,15 @@
-use std::fs::read_to_string;
+use std::{fs::read_to_string, process};
 
 use day9_2::decompressed_size;
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
     let input = input.trim().lines().collect::<Vec<_>>()[0];
-    println!(
-        "Expanded text is {} characters long.",
-        decompressed_size(input)
-    );
+    match decompressed_size(input) {
+        Ok(size) => println!("Expanded text is {size} characters long."),
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    }
 }
@@ -1,16 +1,26 @@
-use std::collections::{BTreeMap, HashSet};
+use std::sync::LazyLock;
 
-use itertools::Itertools;
+use nice_rules::{RuleError, Validator};
 
-fn letter_counts(word: &str) -> BTreeMap<char, usize> {
-    BTreeMap::from_iter(word.chars().counts())
-}
+pub const PART_1_RULES: &str = "no_repeat_words";
+pub const PART_2_RULES: &str = "no_anagram_words";
+
+static PART_2_VALIDATOR: LazyLock<Validator> =
+    LazyLock::new(|| PART_2_RULES.parse().expect("part 2 rules should parse"));
 
 pub fn validate_passphrase(passphrase: &str) -> bool {
-    let mut found_words = HashSet::new();
-    passphrase
-        .split(' ')
-        .all(|word| found_words.insert(letter_counts(word)))
+    PART_2_VALIDATOR.is_valid(passphrase)
+}
+
+pub fn count_valid<'a>(
+    passphrases: impl IntoIterator<Item = &'a str>,
+    rules: &str,
+) -> Result<usize, RuleError> {
+    let validator = rules.parse::<Validator>()?;
+    Ok(passphrases
+        .into_iter()
+        .filter(|passphrase| validator.is_valid(passphrase))
+        .count())
 }
 
 #[cfg(test)]
@@ -53,23 +63,18 @@ mod tests {
     }
 
     #[test]
-    fn letter_counts_test_1() {
-        assert_eq!(letter_counts(""), BTreeMap::new());
-    }
-
-    #[test]
-    fn letter_counts_test_2() {
-        assert_eq!(
-            letter_counts("abcd"),
-            BTreeMap::from_iter([('a', 1), ('b', 1), ('c', 1), ('d', 1)])
-        );
-    }
-
-    #[test]
-    fn letter_counts_test_3() {
-        assert_eq!(
-            letter_counts("aabbbbcddd"),
-            BTreeMap::from_iter([('a', 2), ('b', 4), ('c', 1), ('d', 3)])
-        );
+    fn count_valid_test_1() {
+        // examples from the puzzle description
+        let part_1 = ["aa bb cc dd ee", "aa bb cc dd aa", "aa bb cc dd aaa"];
+        assert_eq!(count_valid(part_1, PART_1_RULES), Ok(2));
+        let part_2 = [
+            "abcde fghij",
+            "abcde xyz ecdab",
+            "a ab abc abd abf abj",
+            "iiii oiii ooii oooi oooo",
+            "oiii ioii iioi iiio",
+        ];
+        assert_eq!(count_valid(part_2, PART_2_RULES), Ok(3));
+        assert_eq!(count_valid(part_2, PART_1_RULES), Ok(5));
     }
 }
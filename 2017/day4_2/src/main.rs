@@ -1,13 +1,18 @@
-use std::fs::read_to_string;
+use std::{env, fs::read_to_string, process};
 
-use day4_2::validate_passphrase;
+use day4_2::{PART_2_RULES, count_valid};
 
 fn main() {
     let input = read_to_string("input.txt").expect("no input.txt in current directory");
     let input: Vec<_> = input.trim().lines().collect();
-    let valid_count = input
-        .iter()
-        .filter(|phrase| validate_passphrase(phrase))
-        .count();
+    // a different rule set can be given as the first argument
+    let rules = env::args().nth(1).unwrap_or(PART_2_RULES.to_owned());
+    let valid_count = match count_valid(input, &rules) {
+        Ok(count) => count,
+        Err(err) => {
+            eprintln!("invalid rules: {err}");
+            process::exit(1);
+        }
+    };
     println!("{valid_count} valid pass phrases");
 }
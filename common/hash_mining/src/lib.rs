@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::num::NonZero;
+use std::ops::Range;
+use std::thread;
+
+pub type Digest = [u8; 16];
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const DEFAULT_BATCH_SIZE: u64 = 1 << 14;
+
+pub fn compute<T: AsRef<[u8]>>(data: T) -> Digest {
+    md5::compute(data).into()
+}
+
+pub fn nibble(digest: &Digest, index: usize) -> u8 {
+    let byte = digest[index / 2];
+    if index.is_multiple_of(2) {
+        byte >> 4
+    } else {
+        byte & 0x0f
+    }
+}
+
+pub fn leading_zero_nibbles(digest: &Digest) -> usize {
+    (0..32).take_while(|&i| nibble(digest, i) == 0).count()
+}
+
+pub fn hex_char(value: u8) -> char {
+    HEX_DIGITS[value as usize] as char
+}
+
+pub fn to_hex(digest: &Digest) -> [u8; 32] {
+    let mut hex = [0; 32];
+    for (i, c) in hex.iter_mut().enumerate() {
+        *c = HEX_DIGITS[nibble(digest, i) as usize];
+    }
+    hex
+}
+
+fn push_decimal(buffer: &mut Vec<u8>, mut number: u64) {
+    let mut digits = [0; 20];
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (number % 10) as u8;
+        number /= 10;
+        if number == 0 {
+            break;
+        }
+    }
+    buffer.extend_from_slice(&digits[start..]);
+}
+
+pub fn worker_count() -> usize {
+    thread::available_parallelism().map_or(1, NonZero::get)
+}
+
+fn split_range(range: Range<u64>, parts: usize) -> Vec<Range<u64>> {
+    let len = range.end.saturating_sub(range.start);
+    let parts = (parts as u64).clamp(1, len.max(1));
+    let chunk = len.div_ceil(parts);
+    (0..parts)
+        .map(|i| {
+            let start = range.start + i * chunk;
+            start.min(range.end)..(start + chunk).min(range.end)
+        })
+        .collect()
+}
+
+// calls `f` with every nonce in `nonces` and the bytes of `prefix` followed by
+// the nonce in decimal, spread over `threads` threads; results are returned in
+// nonce order
+pub fn scan_nonces<T, F>(prefix: &[u8], nonces: Range<u64>, threads: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(u64, &[u8]) -> Option<T> + Sync,
+{
+    let f = &f;
+    thread::scope(|scope| {
+        let workers = split_range(nonces, threads)
+            .into_iter()
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut message = prefix.to_vec();
+                    chunk
+                        .filter_map(|nonce| {
+                            message.truncate(prefix.len());
+                            push_decimal(&mut message, nonce);
+                            f(nonce, &message)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("hashing thread panicked"))
+            .collect()
+    })
+}
+
+pub fn compute_all<T: AsRef<[u8]> + Sync>(inputs: &[T]) -> Vec<Digest> {
+    let ranges = split_range(0..inputs.len() as u64, worker_count());
+    thread::scope(|scope| {
+        let workers = ranges
+            .into_iter()
+            .map(|range| {
+                let chunk = &inputs[range.start as usize..range.end as usize];
+                scope.spawn(move || chunk.iter().map(compute).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("hashing thread panicked"))
+            .collect()
+    })
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Hit {
+    pub nonce: u64,
+    pub digest: Digest,
+}
+
+#[derive(Debug, Clone)]
+pub struct Miner {
+    prefix: Vec<u8>,
+    zeros: usize,
+    next_nonce: u64,
+    threads: usize,
+    batch_size: u64,
+    found: VecDeque<Hit>,
+}
+
+impl Miner {
+    pub fn new(prefix: &str, zeros: usize) -> Miner {
+        Miner {
+            prefix: prefix.as_bytes().to_vec(),
+            zeros,
+            next_nonce: 0,
+            threads: worker_count(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            found: VecDeque::new(),
+        }
+    }
+
+    pub fn starting_at(mut self, nonce: u64) -> Miner {
+        self.next_nonce = nonce;
+        self
+    }
+
+    pub fn with_threads(mut self, threads: NonZero<usize>) -> Miner {
+        self.threads = threads.get();
+        self
+    }
+
+    fn mine_batch(&mut self) -> bool {
+        let start = self.next_nonce;
+        if start == u64::MAX {
+            return false;
+        }
+        let end = start.saturating_add(self.batch_size * self.threads as u64);
+        let zeros = self.zeros;
+        let mut context = md5::Context::new();
+        context.consume(&self.prefix);
+        let context = &context;
+        let prefix_len = self.prefix.len();
+        let hits = scan_nonces(&self.prefix, start..end, self.threads, |nonce, message| {
+            let mut context = context.clone();
+            context.consume(&message[prefix_len..]);
+            let digest: Digest = context.compute().into();
+            (leading_zero_nibbles(&digest) >= zeros).then_some(Hit { nonce, digest })
+        });
+        self.found.extend(hits);
+        self.next_nonce = end;
+        true
+    }
+}
+
+impl Iterator for Miner {
+    type Item = Hit;
+
+    fn next(&mut self) -> Option<Hit> {
+        while self.found.is_empty() {
+            if !self.mine_batch() {
+                return None;
+            }
+        }
+        self.found.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nibble_test_1() {
+        let digest = [0x0a, 0xb1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x7f];
+        assert_eq!(nibble(&digest, 0), 0x0);
+        assert_eq!(nibble(&digest, 1), 0xa);
+        assert_eq!(nibble(&digest, 2), 0xb);
+        assert_eq!(nibble(&digest, 3), 0x1);
+        assert_eq!(nibble(&digest, 31), 0xf);
+    }
+
+    #[test]
+    fn leading_zero_nibbles_test_1() {
+        let digest = compute("abcdef609043");
+        assert_eq!(leading_zero_nibbles(&digest), 5);
+        assert_eq!(leading_zero_nibbles(&[0; 16]), 32);
+    }
+
+    #[test]
+    fn to_hex_test_1() {
+        assert_eq!(
+            &to_hex(&compute("abc")),
+            b"900150983cd24fb0d6963f7d28e17f72"
+        );
+    }
+
+    #[test]
+    fn push_decimal_test_1() {
+        let mut buffer = b"abc".to_vec();
+        push_decimal(&mut buffer, 0);
+        push_decimal(&mut buffer, 18446744073709551615);
+        assert_eq!(buffer, b"abc018446744073709551615");
+    }
+
+    #[test]
+    fn scan_nonces_test_1() {
+        let messages = scan_nonces(b"x", 8..13, 3, |nonce, message| {
+            (nonce != 10).then(|| String::from_utf8(message.to_vec()).unwrap())
+        });
+        assert_eq!(messages, ["x8", "x9", "x11", "x12"]);
+    }
+
+    #[test]
+    fn compute_all_test_1() {
+        let inputs = ["a", "b", "c"];
+        assert_eq!(
+            compute_all(&inputs),
+            inputs.iter().map(compute).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn miner_test_1() {
+        let hits = Miner::new("abcdef", 5)
+            .starting_at(609000)
+            .with_threads(3.try_into().unwrap())
+            .take(1)
+            .collect::<Vec<_>>();
+        assert_eq!(hits[0].nonce, 609043);
+        assert_eq!(hits[0].digest, compute("abcdef609043"));
+    }
+
+    #[test]
+    fn miner_test_2() {
+        let single = Miner::new("abc", 3)
+            .with_threads(1.try_into().unwrap())
+            .take(20)
+            .map(|hit| hit.nonce)
+            .collect::<Vec<_>>();
+        let parallel = Miner::new("abc", 3)
+            .with_threads(4.try_into().unwrap())
+            .take(20)
+            .map(|hit| hit.nonce)
+            .collect::<Vec<_>>();
+        assert_eq!(single, parallel);
+        assert!(single.is_sorted());
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+use itertools::Itertools;
+use thiserror::Error;
+
+// comma separated rules, each negated by a leading `!`, e.g. "vowels>=3, !contains(ab|cd)"
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RuleError {
+    #[error("unknown rule: {0}")]
+    UnknownRule(String),
+    #[error("invalid argument for {rule}: {argument}")]
+    InvalidArgument { rule: String, argument: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    AtLeast,
+    AtMost,
+    Exactly,
+}
+
+impl Comparison {
+    fn holds(self, value: usize, bound: usize) -> bool {
+        match self {
+            Comparison::AtLeast => value >= bound,
+            Comparison::AtMost => value <= bound,
+            Comparison::Exactly => value == bound,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    Vowels(Comparison, usize),
+    Repeat { gap: usize },
+    PairTwice,
+    Contains(Vec<String>),
+    NoRepeatWords,
+    NoAnagramWords,
+    Not(Box<Rule>),
+}
+
+fn letter_counts(word: &str) -> BTreeMap<char, usize> {
+    BTreeMap::from_iter(word.chars().counts())
+}
+
+fn contains_pair_twice(s: &str) -> bool {
+    let pairs = s.chars().zip(s.chars().skip(1));
+    let first_pairs = pairs.clone();
+    for (i, pair) in first_pairs.enumerate() {
+        let mut other_pairs = pairs.clone().skip(i + 2);
+        if other_pairs.any(|other| other == pair) {
+            return true;
+        }
+    }
+    false
+}
+
+fn contains_repeat(s: &str, gap: usize) -> bool {
+    s.chars()
+        .zip(s.chars().skip(gap + 1))
+        .any(|pair| pair.0 == pair.1)
+}
+
+impl Rule {
+    pub fn matches(&self, s: &str) -> bool {
+        match self {
+            Rule::Vowels(comparison, bound) => {
+                let vowels = s.chars().filter(|c| "aeiou".contains(*c)).count();
+                comparison.holds(vowels, *bound)
+            }
+            Rule::Repeat { gap } => contains_repeat(s, *gap),
+            Rule::PairTwice => contains_pair_twice(s),
+            Rule::Contains(needles) => needles.iter().any(|needle| s.contains(needle.as_str())),
+            Rule::NoRepeatWords => {
+                let mut found_words = HashSet::new();
+                s.split(' ').all(|word| found_words.insert(word))
+            }
+            Rule::NoAnagramWords => {
+                let mut found_words = HashSet::new();
+                s.split(' ')
+                    .all(|word| found_words.insert(letter_counts(word)))
+            }
+            Rule::Not(rule) => !rule.matches(s),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(rule) = s.strip_prefix('!') {
+            return Ok(Rule::Not(Box::new(rule.parse()?)));
+        }
+        let invalid = |rule: &str, argument: &str| RuleError::InvalidArgument {
+            rule: rule.to_owned(),
+            argument: argument.to_owned(),
+        };
+        if let Some(bound) = s.strip_prefix("vowels") {
+            let (comparison, count) = [
+                (">=", Comparison::AtLeast),
+                ("<=", Comparison::AtMost),
+                ("==", Comparison::Exactly),
+            ]
+            .into_iter()
+            .find_map(|(op, comparison)| Some((comparison, bound.strip_prefix(op)?)))
+            .ok_or_else(|| invalid("vowels", bound))?;
+            let count = count.trim().parse().map_err(|_| invalid("vowels", count))?;
+            return Ok(Rule::Vowels(comparison, count));
+        }
+        match s {
+            "pair_twice" => return Ok(Rule::PairTwice),
+            "no_repeat_words" => return Ok(Rule::NoRepeatWords),
+            "no_anagram_words" => return Ok(Rule::NoAnagramWords),
+            _ => {}
+        }
+        let (name, argument) = s
+            .strip_suffix(')')
+            .and_then(|call| call.split_once('('))
+            .ok_or_else(|| RuleError::UnknownRule(s.to_owned()))?;
+        match name {
+            "repeat" => {
+                let gap = argument
+                    .strip_prefix("gap=")
+                    .and_then(|gap| gap.trim().parse().ok())
+                    .ok_or_else(|| invalid(name, argument))?;
+                Ok(Rule::Repeat { gap })
+            }
+            "contains" if !argument.is_empty() => Ok(Rule::Contains(
+                argument.split('|').map(str::to_owned).collect(),
+            )),
+            "contains" => Err(invalid(name, argument)),
+            _ => Err(RuleError::UnknownRule(name.to_owned())),
+        }
+    }
+}
+
+// accepts strings matching every rule of the set
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validator {
+    rules: Vec<Rule>,
+}
+
+impl Validator {
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn is_valid(&self, s: &str) -> bool {
+        self.rules.iter().all(|rule| rule.matches(s))
+    }
+}
+
+impl FromStr for Validator {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules = s
+            .split(',')
+            .filter(|rule| !rule.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Validator { rules })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letter_counts_test_1() {
+        assert_eq!(letter_counts(""), BTreeMap::new());
+    }
+
+    #[test]
+    fn letter_counts_test_2() {
+        assert_eq!(
+            letter_counts("abcd"),
+            BTreeMap::from_iter([('a', 1), ('b', 1), ('c', 1), ('d', 1)])
+        );
+    }
+
+    #[test]
+    fn letter_counts_test_3() {
+        assert_eq!(
+            letter_counts("aabbbbcddd"),
+            BTreeMap::from_iter([('a', 2), ('b', 4), ('c', 1), ('d', 3)])
+        );
+    }
+
+    #[test]
+    fn rule_from_str_test_1() {
+        assert_eq!(
+            "vowels>=3".parse::<Rule>(),
+            Ok(Rule::Vowels(Comparison::AtLeast, 3))
+        );
+        assert_eq!("repeat(gap=1)".parse::<Rule>(), Ok(Rule::Repeat { gap: 1 }));
+        assert_eq!(
+            " !contains(ab|cd) ".parse::<Rule>(),
+            Ok(Rule::Not(Box::new(Rule::Contains(vec![
+                "ab".to_owned(),
+                "cd".to_owned()
+            ]))))
+        );
+        assert_eq!("no_anagram_words".parse::<Rule>(), Ok(Rule::NoAnagramWords));
+    }
+
+    #[test]
+    fn rule_from_str_test_2() {
+        assert_eq!(
+            "vowels>3".parse::<Rule>(),
+            Err(RuleError::InvalidArgument {
+                rule: "vowels".to_owned(),
+                argument: ">3".to_owned()
+            })
+        );
+        assert_eq!(
+            "repeat(2)".parse::<Rule>(),
+            Err(RuleError::InvalidArgument {
+                rule: "repeat".to_owned(),
+                argument: "2".to_owned()
+            })
+        );
+        assert_eq!(
+            "palindrome(3)".parse::<Rule>(),
+            Err(RuleError::UnknownRule("palindrome".to_owned()))
+        );
+        assert_eq!(
+            "pairs_twice".parse::<Rule>(),
+            Err(RuleError::UnknownRule("pairs_twice".to_owned()))
+        );
+    }
+
+    #[test]
+    fn rule_matches_test_1() {
+        let rule = |s: &str| s.parse::<Rule>().unwrap();
+        assert!(rule("vowels>=3").matches("aei"));
+        assert!(!rule("vowels>=3").matches("dvszwmarrgswjxmb"));
+        assert!(rule("vowels<=1").matches("xyz"));
+        assert!(rule("repeat(gap=0)").matches("abcdde"));
+        assert!(!rule("repeat(gap=0)").matches("jchzalrnumimnmhp"));
+        assert!(rule("repeat(gap=2)").matches("axyaz"));
+        assert!(rule("!contains(ab|cd|pq|xy)").matches("ugknbfddgicrmopn"));
+        assert!(!rule("!contains(ab|cd|pq|xy)").matches("haegwjzuvuyypxyu"));
+        assert!(rule("no_repeat_words").matches("aa bb cc dd aaa"));
+        assert!(!rule("no_repeat_words").matches("aa bb cc dd aa"));
+        assert!(!rule("no_anagram_words").matches("oiii ioii iioi iiio"));
+    }
+
+    #[test]
+    fn validator_is_valid_test_1() {
+        // examples from 2015 day 5 part 1
+        let validator = "vowels>=3, repeat(gap=0), !contains(ab|cd|pq|xy)"
+            .parse::<Validator>()
+            .unwrap();
+        assert_eq!(validator.rules().len(), 3);
+        assert!(validator.is_valid("ugknbfddgicrmopn"));
+        assert!(validator.is_valid("aaa"));
+        assert!(!validator.is_valid("jchzalrnumimnmhp"));
+        assert!(!validator.is_valid("haegwjzuvuyypxyu"));
+        assert!(!validator.is_valid("dvszwmarrgswjxmb"));
+        assert_eq!(
+            "vowels>=3, pair_twice, shiny".parse::<Validator>(),
+            Err(RuleError::UnknownRule("shiny".to_owned()))
+        );
+        assert!("".parse::<Validator>().unwrap().is_valid("anything"));
+    }
+}